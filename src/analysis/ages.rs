@@ -0,0 +1,125 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Merge, Result, SpentCoin, SpentCoinVisitor};
+
+use super::Analysis;
+
+pub const COUNTS_CSV: &str = "counts.csv";
+pub const INPUT_AGES_CSV: &str = "input_ages.csv";
+
+type Age = u32;
+type Count = u64;
+
+/// The number of blocks between the creation and the spend of each coin. Reports are written to
+/// `counts.csv` and `input_ages.csv` in the output directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CoinAges {
+    include_coinbase: bool,
+    ages: BTreeMap<Age, Count>,
+    block_input_ages: BTreeMap<u32, Vec<Age>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinAgesSummary {
+    pub include_coinbase: bool,
+    pub coins: u64,
+    pub blocks: u64,
+    pub median_age: Option<Age>,
+    pub max_age: Option<Age>,
+}
+
+impl CoinAges {
+    pub fn new(include_coinbase: bool) -> Self {
+        Self {
+            include_coinbase,
+            ..Default::default()
+        }
+    }
+
+    pub fn summary(&self) -> CoinAgesSummary {
+        let coins = self.ages.values().sum::<Count>();
+        let mut seen = 0;
+        let median_age = self.ages.iter().find_map(|(&age, &count)| {
+            seen += count;
+            (seen * 2 > coins).then_some(age)
+        });
+        CoinAgesSummary {
+            include_coinbase: self.include_coinbase,
+            coins,
+            blocks: self.block_input_ages.len() as u64,
+            median_age,
+            max_age: self.ages.keys().next_back().copied(),
+        }
+    }
+}
+
+impl SpentCoinVisitor for CoinAges {
+    fn visit_block(&mut self, height: u32) {
+        self.block_input_ages.insert(height, Vec::new());
+    }
+
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        if !coin.is_coinbase || self.include_coinbase {
+            let age = height - coin.confirmation_height;
+            *self.ages.entry(age).or_insert(0) += 1;
+            self.block_input_ages.entry(height).or_default().push(age);
+        }
+    }
+}
+
+impl Merge for CoinAges {
+    fn merge(&mut self, other: Self) {
+        for (age, count) in other.ages {
+            *self.ages.entry(age).or_insert(0) += count;
+        }
+        self.block_input_ages.extend(other.block_input_ages);
+    }
+}
+
+impl Analysis for CoinAges {
+    fn name(&self) -> &'static str {
+        "ages"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, out_dir: &Path) -> Result<()> {
+        let counts = out_dir.join(COUNTS_CSV);
+        println!("Writing coin age counts to {}", counts.display());
+        write_ages_to_csv(&counts, &self.ages).map_err(|e| Error::csv(&counts, e))?;
+        let input_ages = out_dir.join(INPUT_AGES_CSV);
+        println!("Writing block input ages to {}", input_ages.display());
+        block_input_ages_to_csv(&input_ages, &self.block_input_ages)
+            .map_err(|e| Error::csv(&input_ages, e))
+    }
+}
+
+fn write_ages_to_csv(path: &Path, ages: &BTreeMap<Age, Count>) -> csv::Result<()> {
+    let mut wtr = csv::Writer::from_path(path)?;
+    wtr.write_record(["age", "count"])?;
+    for (k, v) in ages {
+        wtr.write_record([k.to_string(), v.to_string()])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+fn block_input_ages_to_csv(path: &Path, ages: &BTreeMap<u32, Vec<Age>>) -> csv::Result<()> {
+    let mut wtr = csv::Writer::from_path(path)?;
+    wtr.write_record(["block", "ages"])?;
+    for (k, v) in ages {
+        let str_map = v
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join("|");
+        wtr.write_record([k.to_string(), str_map])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor, compact_size, compress_amount, size_varint,
+};
+
+use super::{Analysis, display_bytes};
+
+/// Bytes saved by serializing compressed amounts instead of 8 byte values.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AmountSavings {
+    coins: u64,
+    varint_amount_savings: i64,
+    compact_amount_savings: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountSummary {
+    pub coins: u64,
+    pub varint_savings_bytes: i64,
+    pub compact_size_savings_bytes: i64,
+}
+
+impl AmountSavings {
+    pub fn summary(&self) -> AmountSummary {
+        AmountSummary {
+            coins: self.coins,
+            varint_savings_bytes: self.varint_amount_savings,
+            compact_size_savings_bytes: self.compact_amount_savings,
+        }
+    }
+}
+
+impl SpentCoinVisitor for AmountSavings {
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        let amount = coin.value as u64;
+        let compressed = compress_amount(amount);
+        // Encodings longer than 8 bytes are a loss, which can outweigh the savings of a range.
+        self.varint_amount_savings += 8 - size_varint(compressed) as i64;
+        self.compact_amount_savings += 8 - compact_size(compressed) as i64;
+    }
+}
+
+impl Merge for AmountSavings {
+    fn merge(&mut self, other: Self) {
+        self.coins += other.coins;
+        self.varint_amount_savings += other.varint_amount_savings;
+        self.compact_amount_savings += other.compact_amount_savings;
+    }
+}
+
+impl Analysis for AmountSavings {
+    fn name(&self) -> &'static str {
+        "amounts"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        println!(
+            "Total potential compressed amount savings with VarInt {}",
+            display_bytes(summary.varint_savings_bytes)
+        );
+        println!(
+            "Total potential compressed amount savings with CompactSize {}",
+            display_bytes(summary.compact_size_savings_bytes)
+        );
+        Ok(())
+    }
+}
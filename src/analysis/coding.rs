@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor, compress_amount,
+    range_coder::{BitTree, IntegerModel, RangeEncoder},
+    script::ReconstructableScript,
+    size_varint,
+};
+
+use super::{Analysis, display_bytes};
+
+/// Bits of a script tag.
+const TAG_BITS: u32 = 4;
+
+/// Compares byte-aligned compressed amounts, age and coinbase codes and script tags with the same
+/// fields range coded. Each field of each block is coded on its own with fresh models, so blocks
+/// stay independent messages.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EntropyCoding {
+    coins: u64,
+    amount_bytes: u64,
+    age_bytes: u64,
+    coded: CodedBytes,
+    /// The coins of the block being visited.
+    pending: Vec<PendingCoin>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+struct CodedBytes {
+    amounts: u64,
+    ages: u64,
+    tags: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingCoin {
+    amount: u64,
+    code: u64,
+    tag: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntropyCodingSummary {
+    pub coins: u64,
+    /// Compressed amounts as `VarInt`s.
+    pub amount_bytes: u64,
+    pub amount_coded_bytes: u64,
+    /// `age * 2 + coinbase` as `VarInt`s.
+    pub age_bytes: u64,
+    pub age_coded_bytes: u64,
+    /// One byte per [`ReconstructableScript`] tag.
+    pub tag_bytes: u64,
+    pub tag_coded_bytes: u64,
+}
+
+impl EntropyCoding {
+    pub fn summary(&self) -> EntropyCodingSummary {
+        let pending = code_block(&self.pending);
+        EntropyCodingSummary {
+            coins: self.coins,
+            amount_bytes: self.amount_bytes,
+            amount_coded_bytes: self.coded.amounts + pending.amounts,
+            age_bytes: self.age_bytes,
+            age_coded_bytes: self.coded.ages + pending.ages,
+            tag_bytes: self.coins,
+            tag_coded_bytes: self.coded.tags + pending.tags,
+        }
+    }
+
+    fn flush_pending(&mut self) {
+        let block = code_block(&self.pending);
+        self.coded.add(block);
+        self.pending.clear();
+    }
+}
+
+impl CodedBytes {
+    fn add(&mut self, other: Self) {
+        self.amounts += other.amounts;
+        self.ages += other.ages;
+        self.tags += other.tags;
+    }
+}
+
+fn code_block(coins: &[PendingCoin]) -> CodedBytes {
+    if coins.is_empty() {
+        return CodedBytes::default();
+    }
+    let mut encoder = RangeEncoder::new();
+    let mut model = BitTree::new(TAG_BITS);
+    for coin in coins {
+        model.encode(&mut encoder, coin.tag as u32);
+    }
+    CodedBytes {
+        amounts: code_integers(coins.iter().map(|coin| coin.amount)),
+        ages: code_integers(coins.iter().map(|coin| coin.code)),
+        tags: encoder.finish().len() as u64,
+    }
+}
+
+fn code_integers(values: impl Iterator<Item = u64>) -> u64 {
+    let mut encoder = RangeEncoder::new();
+    let mut model = IntegerModel::new();
+    for value in values {
+        model.encode(&mut encoder, value);
+    }
+    encoder.finish().len() as u64
+}
+
+impl SpentCoinVisitor for EntropyCoding {
+    fn visit_block(&mut self, _height: u32) {
+        self.flush_pending();
+    }
+
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        let amount = compress_amount(coin.value as u64);
+        let age = height.saturating_sub(coin.confirmation_height) as u64;
+        let code = (age << 1) | coin.is_coinbase as u64;
+        self.amount_bytes += size_varint(amount) as u64;
+        self.age_bytes += size_varint(code) as u64;
+        self.pending.push(PendingCoin {
+            amount,
+            code,
+            tag: ReconstructableScript::from_script(&coin.script_pubkey).tag(),
+        });
+    }
+}
+
+impl Merge for EntropyCoding {
+    fn merge(&mut self, other: Self) {
+        self.flush_pending();
+        self.coins += other.coins;
+        self.amount_bytes += other.amount_bytes;
+        self.age_bytes += other.age_bytes;
+        self.coded.add(other.coded);
+        self.pending = other.pending;
+    }
+}
+
+impl Analysis for EntropyCoding {
+    fn name(&self) -> &'static str {
+        "entropy_coding"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        for (field, bytes, coded) in [
+            ("Amounts", summary.amount_bytes, summary.amount_coded_bytes),
+            ("Ages", summary.age_bytes, summary.age_coded_bytes),
+            ("Script tags", summary.tag_bytes, summary.tag_coded_bytes),
+        ] {
+            println!(
+                "{field}: {} byte-aligned, {} range coded",
+                display_bytes(bytes),
+                display_bytes(coded)
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor, compact_size, compress_amount,
+    script::{ScriptType, classify},
+    size_varint,
+};
+
+use super::{Analysis, display_bytes};
+
+/// Frequency tables of the fields of spent coins, split by script type, to measure the empirical
+/// entropy of each field and how much knowing the script type lowers it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Entropy {
+    types: BTreeMap<ScriptType, Tables>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Tables {
+    coins: u64,
+    amounts: Counts,
+    ages: Counts,
+    lengths: Counts,
+}
+
+type Counts = BTreeMap<u64, u64>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntropySummary {
+    pub coins: u64,
+    /// Compressed amounts.
+    pub amounts: FieldEntropy,
+    /// Spending height minus confirmation height.
+    pub ages: FieldEntropy,
+    pub script_types: FieldEntropy,
+    /// Lengths of the scriptPubKeys.
+    pub script_lengths: FieldEntropy,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldEntropy {
+    /// Distinct values seen.
+    pub symbols: u64,
+    /// Zeroth-order entropy in bits per coin.
+    pub entropy_bits: f64,
+    /// The entropy of every coin together, rounded up to whole bytes.
+    pub floor_bytes: u64,
+    /// Entropy in bits per coin when the script type is known.
+    pub given_script_type_bits: f64,
+    pub given_script_type_floor_bytes: u64,
+    pub varint_bytes: u64,
+    pub compact_size_bytes: u64,
+}
+
+impl Entropy {
+    pub fn summary(&self) -> EntropySummary {
+        let coins = self.types.values().map(|tables| tables.coins).sum();
+        let type_counts: Counts = self
+            .types
+            .values()
+            .enumerate()
+            .map(|(i, tables)| (i as u64, tables.coins))
+            .collect();
+        EntropySummary {
+            coins,
+            amounts: self.field(coins, |tables| &tables.amounts),
+            ages: self.field(coins, |tables| &tables.ages),
+            // A tag takes a byte, and nothing is left to code once the type is known.
+            script_types: FieldEntropy::new(coins, &type_counts, std::iter::empty(), coins, coins),
+            script_lengths: self.field(coins, |tables| &tables.lengths),
+        }
+    }
+
+    fn field(&self, coins: u64, table: impl Fn(&Tables) -> &Counts) -> FieldEntropy {
+        let mut all = Counts::new();
+        for tables in self.types.values() {
+            merge_counts(&mut all, table(tables));
+        }
+        let varint = all
+            .iter()
+            .map(|(&value, &count)| size_varint(value) as u64 * count)
+            .sum();
+        let compact_size = all
+            .iter()
+            .map(|(&value, &count)| compact_size(value) as u64 * count)
+            .sum();
+        FieldEntropy::new(
+            coins,
+            &all,
+            self.types.values().map(table),
+            varint,
+            compact_size,
+        )
+    }
+}
+
+impl FieldEntropy {
+    fn new<'a>(
+        coins: u64,
+        all: &Counts,
+        given_script_type: impl Iterator<Item = &'a Counts>,
+        varint_bytes: u64,
+        compact_size_bytes: u64,
+    ) -> Self {
+        let total = total_bits(all);
+        let given: f64 = given_script_type.map(total_bits).sum();
+        let per_coin = |bits: f64| if coins == 0 { 0. } else { bits / coins as f64 };
+        Self {
+            symbols: all.len() as u64,
+            entropy_bits: per_coin(total),
+            floor_bytes: (total / 8.).ceil() as u64,
+            given_script_type_bits: per_coin(given),
+            given_script_type_floor_bytes: (given / 8.).ceil() as u64,
+            varint_bytes,
+            compact_size_bytes,
+        }
+    }
+}
+
+/// The entropy of a sample with the given value counts, in bits for the whole sample.
+fn total_bits(counts: &Counts) -> f64 {
+    let n: u64 = counts.values().sum();
+    if n == 0 {
+        return 0.;
+    }
+    let n = n as f64;
+    let bits = n * n.log2()
+        - counts
+            .values()
+            .map(|&count| count as f64 * (count as f64).log2())
+            .sum::<f64>();
+    bits.max(0.)
+}
+
+fn merge_counts(into: &mut Counts, from: &Counts) {
+    for (&value, &count) in from {
+        *into.entry(value).or_default() += count;
+    }
+}
+
+impl SpentCoinVisitor for Entropy {
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        let tables = self.types.entry(classify(&coin.script_pubkey)).or_default();
+        tables.coins += 1;
+        let amount = compress_amount(coin.value as u64);
+        let age = height.saturating_sub(coin.confirmation_height) as u64;
+        *tables.amounts.entry(amount).or_default() += 1;
+        *tables.ages.entry(age).or_default() += 1;
+        *tables
+            .lengths
+            .entry(coin.script_pubkey.len() as u64)
+            .or_default() += 1;
+    }
+}
+
+impl Merge for Entropy {
+    fn merge(&mut self, other: Self) {
+        for (script_type, other) in other.types {
+            let tables = self.types.entry(script_type).or_default();
+            tables.coins += other.coins;
+            merge_counts(&mut tables.amounts, &other.amounts);
+            merge_counts(&mut tables.ages, &other.ages);
+            merge_counts(&mut tables.lengths, &other.lengths);
+        }
+    }
+}
+
+impl Analysis for Entropy {
+    fn name(&self) -> &'static str {
+        "entropy"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        println!("Coins: {}", summary.coins);
+        for (field, entropy) in [
+            ("Amounts", &summary.amounts),
+            ("Ages", &summary.ages),
+            ("Script types", &summary.script_types),
+            ("Script lengths", &summary.script_lengths),
+        ] {
+            println!("{field}: {} distinct values", entropy.symbols);
+            println!(
+                "  Entropy: {:.3} bits per coin, {}",
+                entropy.entropy_bits,
+                display_bytes(entropy.floor_bytes)
+            );
+            println!(
+                "  Given the script type: {:.3} bits per coin, {}",
+                entropy.given_script_type_bits,
+                display_bytes(entropy.given_script_type_floor_bytes)
+            );
+            println!("  VarInt: {}", display_bytes(entropy.varint_bytes));
+            println!(
+                "  CompactSize: {}",
+                display_bytes(entropy.compact_size_bytes)
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Merge, Result, SpentCoin, SpentCoinVisitor, compact_size, size_varint};
+
+use super::{Analysis, display_bytes};
+
+/// Bytes needed to send the confirmation height of every spent coin under several encodings.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HeightEncodings {
+    coins: u64,
+    varint: u64,
+    compact_size: u64,
+    height_coinbase_varint: u64,
+    age_varint: u64,
+    age_coinbase_varint: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeightEncodingsSummary {
+    pub coins: u64,
+    /// The height as a 4 byte little-endian integer.
+    pub u32_bytes: u64,
+    pub varint_bytes: u64,
+    pub compact_size_bytes: u64,
+    /// `height * 2 + coinbase` as a `VarInt`, as in Core's undo data.
+    pub height_coinbase_varint_bytes: u64,
+    /// The age, the spending height minus the confirmation height, as a `VarInt`.
+    pub age_varint_bytes: u64,
+    /// `age * 2 + coinbase` as a `VarInt`.
+    pub age_coinbase_varint_bytes: u64,
+}
+
+impl HeightEncodings {
+    pub fn summary(&self) -> HeightEncodingsSummary {
+        HeightEncodingsSummary {
+            coins: self.coins,
+            u32_bytes: self.coins * 4,
+            varint_bytes: self.varint,
+            compact_size_bytes: self.compact_size,
+            height_coinbase_varint_bytes: self.height_coinbase_varint,
+            age_varint_bytes: self.age_varint,
+            age_coinbase_varint_bytes: self.age_coinbase_varint,
+        }
+    }
+}
+
+impl SpentCoinVisitor for HeightEncodings {
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        let confirmation = coin.confirmation_height as u64;
+        let age = height.saturating_sub(coin.confirmation_height) as u64;
+        let coinbase = coin.is_coinbase as u64;
+        self.varint += size_varint(confirmation) as u64;
+        self.compact_size += compact_size(confirmation) as u64;
+        self.height_coinbase_varint += size_varint(confirmation * 2 + coinbase) as u64;
+        self.age_varint += size_varint(age) as u64;
+        self.age_coinbase_varint += size_varint(age * 2 + coinbase) as u64;
+    }
+}
+
+impl Merge for HeightEncodings {
+    fn merge(&mut self, other: Self) {
+        self.coins += other.coins;
+        self.varint += other.varint;
+        self.compact_size += other.compact_size;
+        self.height_coinbase_varint += other.height_coinbase_varint;
+        self.age_varint += other.age_varint;
+        self.age_coinbase_varint += other.age_coinbase_varint;
+    }
+}
+
+impl Analysis for HeightEncodings {
+    fn name(&self) -> &'static str {
+        "heights"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        println!("Height as u32: {}", display_bytes(summary.u32_bytes));
+        println!("Height as VarInt: {}", display_bytes(summary.varint_bytes));
+        println!(
+            "Height as CompactSize: {}",
+            display_bytes(summary.compact_size_bytes)
+        );
+        println!(
+            "Height and coinbase flag as VarInt: {}",
+            display_bytes(summary.height_coinbase_varint_bytes)
+        );
+        println!("Age as VarInt: {}", display_bytes(summary.age_varint_bytes));
+        println!(
+            "Age and coinbase flag as VarInt: {}",
+            display_bytes(summary.age_coinbase_varint_bytes)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    any::Any,
+    io::{Read, Write},
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{Checkpoint, Merge, Result, SpentCoin, SpentCoinVisitor};
+
+mod ages;
+mod amounts;
+mod coding;
+mod entropy;
+mod heights;
+mod multisig;
+mod p2pk;
+mod script_types;
+mod scripts;
+
+pub use ages::{COUNTS_CSV, CoinAges, CoinAgesSummary, INPUT_AGES_CSV};
+pub use amounts::{AmountSavings, AmountSummary};
+pub use coding::{EntropyCoding, EntropyCodingSummary};
+pub use entropy::{Entropy, EntropySummary, FieldEntropy};
+pub use heights::{HeightEncodings, HeightEncodingsSummary};
+pub use multisig::{BareMultisig, BareMultisigSummary};
+pub use p2pk::{UncompressedP2pk, UncompressedP2pkSummary};
+pub use script_types::{ScriptTypeCount, ScriptTypes, ScriptTypesSummary};
+pub use scripts::{ScriptSavings, ScriptSummary};
+
+/// A spent-coin visitor that summarizes its results once the walk is complete.
+pub trait Analysis: SpentCoinVisitor + MergeBoxed + Checkpoint + Send + Any {
+    /// Identifies the analysis in machine-readable reports.
+    fn name(&self) -> &'static str;
+
+    /// The exact results, for machine-readable reports.
+    fn results(&self) -> Value;
+
+    /// Print the results, writing any tables to `out_dir`.
+    fn report(&self, out_dir: &Path) -> Result<()>;
+}
+
+fn display_bytes(bytes: impl Into<i128>) -> String {
+    let bytes = bytes.into();
+    format!("{:.2}MB ({bytes} bytes)", bytes as f64 / 1_000_000.)
+}
+
+/// Size of a spent coin with a 4 byte height, 8 byte amount and the raw script.
+fn undo_size(coin: &SpentCoin) -> u64 {
+    (coin.confirmation_height.to_le_bytes().len()
+        + coin.value.to_le_bytes().len()
+        + coin.script_pubkey.len()) as u64
+}
+
+/// [`Merge`] for analyses behind a `Box<dyn Analysis>`.
+pub trait MergeBoxed {
+    /// Merge another instance of the same analysis type into this one.
+    fn merge_boxed(&mut self, other: Box<dyn Analysis>);
+}
+
+impl<T: Merge + Any> MergeBoxed for T {
+    fn merge_boxed(&mut self, other: Box<dyn Analysis>) {
+        let other: Box<dyn Any> = other;
+        let other = other
+            .downcast::<T>()
+            .expect("analyses are merged with the same type.");
+        self.merge(*other);
+    }
+}
+
+/// Fans every block and coin out to each registered analysis, so they share one chain traversal.
+#[derive(Default)]
+pub struct Analyses {
+    analyses: Vec<Box<dyn Analysis>>,
+}
+
+impl Analyses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, analysis: impl Analysis + 'static) {
+        self.analyses.push(Box::new(analysis));
+    }
+}
+
+impl SpentCoinVisitor for Analyses {
+    fn visit_block(&mut self, height: u32) {
+        for analysis in &mut self.analyses {
+            analysis.visit_block(height);
+        }
+    }
+
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        for analysis in &mut self.analyses {
+            analysis.visit_coin(height, coin);
+        }
+    }
+}
+
+impl Merge for Analyses {
+    fn merge(&mut self, other: Self) {
+        assert_eq!(self.analyses.len(), other.analyses.len());
+        for (analysis, other) in self.analyses.iter_mut().zip(other.analyses) {
+            analysis.merge_boxed(other);
+        }
+    }
+}
+
+/// The state of each analysis in registration order, one JSON value per line.
+impl Checkpoint for Analyses {
+    fn save(&self, writer: &mut dyn Write) -> Result<(), serde_json::Error> {
+        for analysis in &self.analyses {
+            analysis.save(writer)?;
+            writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self, reader: &mut dyn Read) -> Result<(), serde_json::Error> {
+        for analysis in &mut self.analyses {
+            analysis.restore(reader)?;
+        }
+        Ok(())
+    }
+}
+
+impl Analysis for Analyses {
+    fn name(&self) -> &'static str {
+        "all"
+    }
+
+    /// The results of each analysis, keyed by name.
+    fn results(&self) -> Value {
+        let results = self
+            .analyses
+            .iter()
+            .map(|a| (a.name().to_string(), a.results()))
+            .collect();
+        Value::Object(results)
+    }
+
+    fn report(&self, out_dir: &Path) -> Result<()> {
+        for analysis in &self.analyses {
+            analysis.report(out_dir)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor, compact_size,
+    script::{ReconstructableScript, ScriptType, classify},
+};
+
+use super::{Analysis, display_bytes, undo_size};
+
+/// Counts spent bare multisig outputs and the bytes saved by encoding them as `m`, `n` and
+/// compressed keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BareMultisig {
+    coins: u64,
+    multisig: u64,
+    compressible: u64,
+    undo_bytes: u64,
+    script_bytes: u64,
+    savings_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BareMultisigSummary {
+    pub coins: u64,
+    pub multisig: u64,
+    /// Outputs whose keys are all compressed, or uncompressed points on the curve.
+    pub compressible: u64,
+    /// Outputs with a hybrid or invalid key, which must be sent raw.
+    pub raw: u64,
+    /// Size of the multisig coins with a 4 byte height, 8 byte amount and the raw script.
+    pub undo_bytes: u64,
+    pub script_bytes: u64,
+    /// Bytes saved over `CompactSize` prefixed scripts.
+    pub savings_bytes: u64,
+}
+
+impl BareMultisig {
+    pub fn summary(&self) -> BareMultisigSummary {
+        BareMultisigSummary {
+            coins: self.coins,
+            multisig: self.multisig,
+            compressible: self.compressible,
+            raw: self.multisig - self.compressible,
+            undo_bytes: self.undo_bytes,
+            script_bytes: self.script_bytes,
+            savings_bytes: self.savings_bytes,
+        }
+    }
+}
+
+impl SpentCoinVisitor for BareMultisig {
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        let script = &coin.script_pubkey;
+        if !matches!(classify(script), ScriptType::Multisig { .. }) {
+            return;
+        }
+        self.multisig += 1;
+        self.undo_bytes += undo_size(coin);
+        self.script_bytes += script.len() as u64;
+        let reconstructable = ReconstructableScript::from_script(script);
+        if let ReconstructableScript::Multisig { .. } = reconstructable {
+            self.compressible += 1;
+            let raw_len = compact_size(script.len() as u64) + script.len();
+            self.savings_bytes += (raw_len - reconstructable.encoded_len()) as u64;
+        }
+    }
+}
+
+impl Merge for BareMultisig {
+    fn merge(&mut self, other: Self) {
+        self.coins += other.coins;
+        self.multisig += other.multisig;
+        self.compressible += other.compressible;
+        self.undo_bytes += other.undo_bytes;
+        self.script_bytes += other.script_bytes;
+        self.savings_bytes += other.savings_bytes;
+    }
+}
+
+impl Analysis for BareMultisig {
+    fn name(&self) -> &'static str {
+        "multisig"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        println!(
+            "Bare multisig: {} of {} coins",
+            summary.multisig, summary.coins
+        );
+        println!("Compressible: {}", summary.compressible);
+        println!("Hybrid or invalid keys, sent raw: {}", summary.raw);
+        println!("Undo size: {}", display_bytes(summary.undo_bytes));
+        println!("Script size: {}", display_bytes(summary.script_bytes));
+        println!("Savings: {}", display_bytes(summary.savings_bytes));
+        Ok(())
+    }
+}
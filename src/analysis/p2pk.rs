@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor,
+    script::{ReconstructableScript, ScriptType, classify},
+};
+
+use super::{Analysis, display_bytes};
+
+/// Counts spent P2PK outputs with a 65 byte public key, and how many of the keys compress
+/// losslessly.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UncompressedP2pk {
+    coins: u64,
+    compressible: u64,
+    hybrid: u64,
+    invalid: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UncompressedP2pkSummary {
+    pub coins: u64,
+    pub uncompressed_p2pk: u64,
+    /// Keys that are points on the curve, so that y may be recovered from x and its parity.
+    pub compressible: u64,
+    /// Keys with a `0x06` or `0x07` prefix, which must be sent raw.
+    pub hybrid: u64,
+    /// Keys with a `0x04` prefix that are not points on the curve, which must be sent raw.
+    pub invalid: u64,
+    /// Compressing each compressible key drops the 32 byte y-coordinate.
+    pub savings_bytes: u64,
+}
+
+impl UncompressedP2pk {
+    pub fn summary(&self) -> UncompressedP2pkSummary {
+        UncompressedP2pkSummary {
+            coins: self.coins,
+            uncompressed_p2pk: self.compressible + self.hybrid + self.invalid,
+            compressible: self.compressible,
+            hybrid: self.hybrid,
+            invalid: self.invalid,
+            savings_bytes: self.compressible * 32,
+        }
+    }
+}
+
+impl SpentCoinVisitor for UncompressedP2pk {
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        let script = &coin.script_pubkey;
+        match classify(script) {
+            ScriptType::P2pkUncompressed => match ReconstructableScript::from_script(script) {
+                ReconstructableScript::P2pkUncompressed(_) => self.compressible += 1,
+                _ => self.invalid += 1,
+            },
+            ScriptType::P2pkHybrid => self.hybrid += 1,
+            _ => {}
+        }
+    }
+}
+
+impl Merge for UncompressedP2pk {
+    fn merge(&mut self, other: Self) {
+        self.coins += other.coins;
+        self.compressible += other.compressible;
+        self.hybrid += other.hybrid;
+        self.invalid += other.invalid;
+    }
+}
+
+impl Analysis for UncompressedP2pk {
+    fn name(&self) -> &'static str {
+        "p2pk"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        println!("Uncompressed P2PK: {}", summary.uncompressed_p2pk);
+        println!("Compressible: {}", summary.compressible);
+        println!("Hybrid, sent raw: {}", summary.hybrid);
+        println!("Not on the curve, sent raw: {}", summary.invalid);
+        println!(
+            "Total uncompressed P2PK compression availability: {}",
+            display_bytes(summary.savings_bytes)
+        );
+        Ok(())
+    }
+}
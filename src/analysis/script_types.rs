@@ -0,0 +1,85 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor,
+    script::{ScriptType, classify},
+};
+
+use super::{Analysis, display_bytes};
+
+/// Counts spent coins and the size of their scripts by [`ScriptType`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScriptTypes {
+    coins: u64,
+    types: BTreeMap<ScriptType, ScriptTypeCount>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptTypeCount {
+    pub coins: u64,
+    pub script_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptTypesSummary {
+    pub coins: u64,
+    pub script_bytes: u64,
+    pub types: BTreeMap<ScriptType, ScriptTypeCount>,
+}
+
+impl ScriptTypes {
+    pub fn summary(&self) -> ScriptTypesSummary {
+        ScriptTypesSummary {
+            coins: self.coins,
+            script_bytes: self.types.values().map(|count| count.script_bytes).sum(),
+            types: self.types.clone(),
+        }
+    }
+}
+
+impl SpentCoinVisitor for ScriptTypes {
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        let count = self.types.entry(classify(&coin.script_pubkey)).or_default();
+        count.coins += 1;
+        count.script_bytes += coin.script_pubkey.len() as u64;
+    }
+}
+
+impl Merge for ScriptTypes {
+    fn merge(&mut self, other: Self) {
+        self.coins += other.coins;
+        for (script_type, other) in other.types {
+            let count = self.types.entry(script_type).or_default();
+            count.coins += other.coins;
+            count.script_bytes += other.script_bytes;
+        }
+    }
+}
+
+impl Analysis for ScriptTypes {
+    fn name(&self) -> &'static str {
+        "script_types"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        for (script_type, count) in &summary.types {
+            println!(
+                "{script_type}: {} coins, {}",
+                count.coins,
+                display_bytes(count.script_bytes)
+            );
+        }
+        println!("Total: {} coins", summary.coins);
+        println!("Total script size: {}", display_bytes(summary.script_bytes));
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Merge, Result, SpentCoin, SpentCoinVisitor, compact_size, script::ReconstructableScript,
+};
+
+use super::{Analysis, display_bytes, undo_size};
+
+/// Bytes saved by the [`ReconstructableScript`] format over a `CompactSize` prefixed script, broken
+/// down by script template.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScriptSavings {
+    coins: u64,
+    total_undo_size: u128,
+    total_savings_bytes: u128,
+    total_extra: u128,
+    total_p2tr: u128,
+    total_p2wpkh: u128,
+    total_p2wsh: u128,
+    total_p2pk: u128,
+    total_p2pkh: u128,
+    total_p2sh: u128,
+    total_p2pk_uncompressed: u128,
+    total_multisig: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptSummary {
+    pub coins: u64,
+    /// Size of the spent coins with a 4 byte height, 8 byte amount and the raw script.
+    pub undo_bytes: u64,
+    pub savings_bytes: u64,
+    /// Coins with a script that matches no template, each costing an extra byte.
+    pub unknown_scripts: u64,
+    pub p2tr_savings_bytes: u64,
+    pub p2wpkh_savings_bytes: u64,
+    pub p2wsh_savings_bytes: u64,
+    pub p2pk_savings_bytes: u64,
+    pub p2pkh_savings_bytes: u64,
+    pub p2sh_savings_bytes: u64,
+    pub p2pk_uncompressed_savings_bytes: u64,
+    pub multisig_savings_bytes: u64,
+}
+
+impl ScriptSavings {
+    pub fn summary(&self) -> ScriptSummary {
+        ScriptSummary {
+            coins: self.coins,
+            undo_bytes: self.total_undo_size as u64,
+            savings_bytes: self.total_savings_bytes as u64,
+            unknown_scripts: self.total_extra as u64,
+            p2tr_savings_bytes: self.total_p2tr as u64,
+            p2wpkh_savings_bytes: self.total_p2wpkh as u64,
+            p2wsh_savings_bytes: self.total_p2wsh as u64,
+            p2pk_savings_bytes: self.total_p2pk as u64,
+            p2pkh_savings_bytes: self.total_p2pkh as u64,
+            p2sh_savings_bytes: self.total_p2sh as u64,
+            p2pk_uncompressed_savings_bytes: self.total_p2pk_uncompressed as u64,
+            multisig_savings_bytes: self.total_multisig as u64,
+        }
+    }
+}
+
+impl SpentCoinVisitor for ScriptSavings {
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.coins += 1;
+        self.total_undo_size += undo_size(coin) as u128;
+        let bytes = &coin.script_pubkey;
+        let raw_len = compact_size(bytes.len() as u64) + bytes.len();
+        let script = ReconstructableScript::from_script(bytes);
+        let saved = raw_len.saturating_sub(script.encoded_len()) as u128;
+        self.total_savings_bytes += saved;
+        match script {
+            ReconstructableScript::P2pkh(_) => self.total_p2pkh += saved,
+            ReconstructableScript::P2sh(_) => self.total_p2sh += saved,
+            ReconstructableScript::P2pk(_) => self.total_p2pk += saved,
+            ReconstructableScript::P2pkUncompressed(_) => self.total_p2pk_uncompressed += saved,
+            ReconstructableScript::P2wpkh(_) => self.total_p2wpkh += saved,
+            ReconstructableScript::P2wsh(_) => self.total_p2wsh += saved,
+            ReconstructableScript::P2tr(_) => self.total_p2tr += saved,
+            ReconstructableScript::Multisig { .. } => self.total_multisig += saved,
+            ReconstructableScript::Other(_) => self.total_extra += 1,
+        }
+    }
+}
+
+impl Merge for ScriptSavings {
+    fn merge(&mut self, other: Self) {
+        self.coins += other.coins;
+        self.total_undo_size += other.total_undo_size;
+        self.total_savings_bytes += other.total_savings_bytes;
+        self.total_extra += other.total_extra;
+        self.total_p2tr += other.total_p2tr;
+        self.total_p2wpkh += other.total_p2wpkh;
+        self.total_p2wsh += other.total_p2wsh;
+        self.total_p2pk += other.total_p2pk;
+        self.total_p2pkh += other.total_p2pkh;
+        self.total_p2sh += other.total_p2sh;
+        self.total_p2pk_uncompressed += other.total_p2pk_uncompressed;
+        self.total_multisig += other.total_multisig;
+    }
+}
+
+impl Analysis for ScriptSavings {
+    fn name(&self) -> &'static str {
+        "scripts"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self.summary()).expect("summaries are plain data")
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        let summary = self.summary();
+        println!("Savings: {}", display_bytes(summary.savings_bytes));
+        println!("P2TR: {}", display_bytes(summary.p2tr_savings_bytes));
+        println!("P2WPKH: {}", display_bytes(summary.p2wpkh_savings_bytes));
+        println!("P2WSH: {}", display_bytes(summary.p2wsh_savings_bytes));
+        println!("P2SH: {}", display_bytes(summary.p2sh_savings_bytes));
+        println!("P2PKH: {}", display_bytes(summary.p2pkh_savings_bytes));
+        println!("P2PK: {}", display_bytes(summary.p2pk_savings_bytes));
+        println!(
+            "P2PK Uncompressed: {}",
+            display_bytes(summary.p2pk_uncompressed_savings_bytes)
+        );
+        println!(
+            "Bare multisig: {}",
+            display_bytes(summary.multisig_savings_bytes)
+        );
+        println!("Count unknown scripts: {}", summary.unknown_scripts);
+        println!("Total undo size: {}", display_bytes(summary.undo_bytes));
+        Ok(())
+    }
+}
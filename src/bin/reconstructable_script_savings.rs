@@ -1,132 +0,0 @@
-use kernel::{
-    ChainType, ChainstateManager, CoinRef, ContextBuilder,
-    core::{BlockSpentOutputsExt, CoinExt, ScriptPubkeyExt, TransactionSpentOutputsExt, TxOutExt},
-};
-use std::path::PathBuf;
-
-fn main() {
-    let bitcoin_dir = std::env::var("BITCOIN_DIR").unwrap();
-    println!("Using directory {bitcoin_dir}");
-    let data_dir = bitcoin_dir.parse::<PathBuf>().unwrap();
-    let blocks_dir = data_dir.join("blocks");
-    let context = ContextBuilder::new()
-        .chain_type(ChainType::Mainnet)
-        .build()
-        .unwrap();
-    let chainman = ChainstateManager::new(
-        &context,
-        data_dir.to_str().unwrap(),
-        blocks_dir.to_str().unwrap(),
-    )
-    .unwrap();
-    chainman.import_blocks().unwrap();
-    let mut total_undo_size: u128 = 0;
-    let mut total_savings_bytes: u128 = 0;
-    let mut total_extra: u128 = 0;
-    let mut total_p2tr: u128 = 0;
-    let mut total_p2wpkh: u128 = 0;
-    let mut total_p2wsh: u128 = 0;
-    let mut total_p2pk: u128 = 0;
-    let mut total_p2pkh: u128 = 0;
-    let mut total_p2sh: u128 = 0;
-    let mut total_p2pk_uncompressed: u128 = 0;
-    let chain = chainman.active_chain();
-    for entry in chain.iter() {
-        println!(
-            "Block hash {} at height {}",
-            entry.block_hash(),
-            entry.height()
-        );
-        let block = chainman.read_spent_outputs(&entry).unwrap();
-        for output in block.iter() {
-            for coin in output.coins() {
-                total_undo_size += coin.size_bytes();
-                let tx_out = coin.output();
-                let spk = tx_out.script_pubkey();
-                let bytes = spk.to_bytes();
-                // Each script type here 1. swaps compact size prefix in lieu of a prefix byte 2.
-                // removes all OP codes in the script, which are now implied.
-                // P2TR
-                if bytes.len() == 34 && bytes[0] == 0x51 && bytes[1] == 0x20 {
-                    total_savings_bytes += 2;
-                    total_p2tr += 2;
-                    continue;
-                }
-                // P2WPKH
-                if bytes.len() == 22 && bytes[0] == 0x00 && bytes[1] == 0x14 {
-                    total_savings_bytes += 2;
-                    total_p2wpkh += 2;
-                    continue;
-                }
-                // P2WSH
-                if bytes.len() == 34 && bytes[0] == 0x00 && bytes[1] == 0x20 {
-                    total_savings_bytes += 2;
-                    total_p2wsh += 2;
-                    continue;
-                }
-                // P2PK
-                if bytes.len() == 35 && bytes[0] == 33 && bytes[34] == 0xAC {
-                    total_savings_bytes += 3;
-                    total_p2pk += 3;
-                    continue;
-                }
-                // P2PKH
-                if bytes.len() == 25
-                    && bytes[0] == 0x76
-                    && bytes[1] == 0xA9
-                    && bytes[2] == 20
-                    && bytes[23] == 0x88
-                    && bytes[24] == 0xAC
-                {
-                    total_savings_bytes += 5;
-                    total_p2pkh += 5;
-                    continue;
-                }
-                // P2SH
-                if bytes.len() == 23 && bytes[0] == 0xA9 && bytes[1] == 0x14 && bytes[22] == 0x87 {
-                    total_savings_bytes += 3;
-                    total_p2sh += 3;
-                    continue;
-                }
-                // P2PK Uncompressed
-                if bytes.len() == 67 && bytes[0] == 65 && bytes[66] == 0xAC {
-                    total_savings_bytes += 3;
-                    total_p2pk_uncompressed += 3;
-                    continue;
-                }
-                total_extra += 1
-            }
-        }
-        if swiftsync_research::is_reference_height(entry) {
-            break;
-        }
-    }
-    println!("Savings: {}MB", total_savings_bytes / 1_000_000);
-    println!("P2TR: {}MB", total_p2tr / 1_000_000);
-    println!("P2WPKH: {}MB", total_p2wpkh / 1_000_000);
-    println!("P2WSH: {}MB", total_p2wsh / 1_000_000);
-    println!("P2SH: {}MB", total_p2sh / 1_000_000);
-    println!("P2PKH: {}MB", total_p2pkh / 1_000_000);
-    println!("P2PK: {}MB", total_p2pk / 1_000_000);
-    println!(
-        "P2PK Uncompressed: {}MB",
-        total_p2pk_uncompressed / 1_000_000
-    );
-    println!("Count unknown scripts: {}", total_extra);
-    println!("Total undo size: {}GB", total_undo_size / 1_000_000_000);
-}
-
-trait SizeExt {
-    fn size_bytes(&self) -> u128;
-}
-
-#[allow(clippy::extra_unused_lifetimes)]
-impl<'a> SizeExt for CoinRef<'_> {
-    fn size_bytes(&self) -> u128 {
-        let mut total = 0u128;
-        total += self.confirmation_height().to_le_bytes().len() as u128;
-        total += self.output().script_pubkey().to_bytes().len() as u128;
-        total += self.output().value().to_le_bytes().len() as u128;
-        total
-    }
-}
@@ -0,0 +1,246 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{AnalysisArgs, analyze};
+use csv::{Reader, StringRecord};
+use plotters::prelude::*;
+use swiftsync_research::{
+    Error, Result,
+    analysis::{COUNTS_CSV, CoinAges, INPUT_AGES_CSV},
+};
+
+const PERCENTAGES_CSV: &str = "percentages.csv";
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Count the ages of spent coins, writing `counts.csv` and `input_ages.csv`.
+    Compute {
+        #[command(flatten)]
+        args: AnalysisArgs,
+        /// Count coinbase outputs as well.
+        #[arg(long)]
+        include_coinbase: bool,
+        /// Directory to write the tables to.
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Summarize how recently the inputs of each block were created, from `input_ages.csv`.
+    Inputs {
+        /// Directory holding `input_ages.csv`, where `percentages.csv` is written.
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Plot the distribution of coin ages from `counts.csv`.
+    Plot {
+        /// Directory holding `counts.csv`.
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+        /// Leave out coins older than this many blocks.
+        #[arg(long, value_name = "BLOCKS")]
+        max_age: Option<u32>,
+        /// Path of the image to write.
+        #[arg(long, value_name = "FILE", default_value = "plot.png")]
+        output: PathBuf,
+    },
+}
+
+impl Command {
+    pub fn run(self) -> Result<()> {
+        match self {
+            Self::Compute {
+                args,
+                include_coinbase,
+                dir,
+            } => analyze(&args, &dir, || CoinAges::new(include_coinbase)),
+            Self::Inputs { dir } => {
+                read_input_ages(&dir.join(INPUT_AGES_CSV), &dir.join(PERCENTAGES_CSV))
+            }
+            Self::Plot {
+                dir,
+                max_age,
+                output,
+            } => {
+                let map = read_csv_to_btreemap(&dir.join(COUNTS_CSV), max_age)?;
+                plot_age_count(map, &output).map_err(|e| Error::Plot(e.to_string()))
+            }
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Row {
+    age: u32,
+    count: u64,
+}
+
+fn percent_in_age_range(input_ages: &[u32], filter: u32) -> f64 {
+    let count = input_ages.iter().filter(|&age| *age <= filter).count();
+    count as f64 / input_ages.len() as f64
+}
+
+fn average(percentages: &[f64]) -> Option<f64> {
+    (!percentages.is_empty()).then(|| percentages.iter().sum::<f64>() / percentages.len() as f64)
+}
+
+fn median(percentages: &mut [f64]) -> Option<f64> {
+    percentages.sort_by(f64::total_cmp);
+    let mid = percentages.len() / 2;
+    match percentages.len() {
+        0 => None,
+        len if len.is_multiple_of(2) => Some((percentages[mid - 1] + percentages[mid]) / 2.),
+        _ => Some(percentages[mid]),
+    }
+}
+
+fn print_summary(blocks: u32, percentages: &mut [f64]) {
+    match (average(percentages), median(percentages)) {
+        (Some(average), Some(median)) => {
+            println!("percentage {blocks} blocks or earlier {average:.4}, median: {median:.4}")
+        }
+        _ => println!("percentage {blocks} blocks or earlier: no data"),
+    }
+}
+
+fn read_input_ages(path: &Path, output: &Path) -> Result<()> {
+    let mut rdr = Reader::from_path(path).map_err(|e| missing_input(path, e))?;
+    let mut wtr = csv::Writer::from_path(output).map_err(|e| Error::csv(output, e))?;
+    wtr.write_record([
+        "block",
+        "percent 5 blocks old",
+        "percent 10 blocks old",
+        "percent 50 blocks old",
+        "percent 100 blocks old",
+    ])
+    .map_err(|e| Error::csv(output, e))?;
+    let mut total_5 = Vec::new();
+    let mut total_10 = Vec::new();
+    let mut total_50 = Vec::new();
+    let mut total_100 = Vec::new();
+    for result in rdr.records() {
+        let record: StringRecord = result.map_err(|e| Error::csv(path, e))?;
+        let block = parse_field::<u32>(path, &record[0])?;
+        if block < 100_000 {
+            continue;
+        }
+        let ages = &record[1];
+        if ages.is_empty() {
+            continue;
+        }
+        let ages = ages
+            .split("|")
+            .map(|age| parse_field::<u32>(path, age))
+            .collect::<Result<Vec<u32>>>()?;
+        let percent_are_5 = percent_in_age_range(&ages, 5);
+        let percent_are_10 = percent_in_age_range(&ages, 10);
+        let percent_are_50 = percent_in_age_range(&ages, 50);
+        let percent_are_100 = percent_in_age_range(&ages, 100);
+        println!(
+            "block {block}: percentage in block lifespan; 5: {percent_are_5:.4}, 10: {percent_are_10:.4}, 50: {percent_are_50:.4}, 100: {percent_are_100:.4}"
+        );
+        total_5.push(percent_are_5);
+        total_10.push(percent_are_10);
+        total_50.push(percent_are_50);
+        total_100.push(percent_are_100);
+        wtr.write_record([
+            block.to_string(),
+            format!("{:.4}", percent_are_5),
+            format!("{:.4}", percent_are_10),
+            format!("{:.4}", percent_are_50),
+            format!("{:.4}", percent_are_100),
+        ])
+        .map_err(|e| Error::csv(output, e))?;
+    }
+    println!(" ");
+    println!(">>> Summary >>>");
+    println!(" ");
+    print_summary(5, &mut total_5);
+    print_summary(10, &mut total_10);
+    print_summary(50, &mut total_50);
+    print_summary(100, &mut total_100);
+    println!(" ");
+    println!("Flushed results to `{}`", output.display());
+    wtr.flush().map_err(|e| Error::io(output, e))
+}
+
+fn missing_input(path: &Path, e: csv::Error) -> Error {
+    Error::Config(format!(
+        "could not read `{}` ({e}), run `swiftsync ages compute` first to generate it",
+        path.display()
+    ))
+}
+
+fn parse_field<T: std::str::FromStr>(path: &Path, field: &str) -> Result<T> {
+    field.parse().map_err(|_| {
+        Error::Config(format!(
+            "`{}` has a malformed field `{field}`",
+            path.display()
+        ))
+    })
+}
+
+fn read_csv_to_btreemap(path: &Path, filter: Option<u32>) -> Result<BTreeMap<u32, u64>> {
+    let mut map = BTreeMap::new();
+    let mut rdr = Reader::from_path(path).map_err(|e| missing_input(path, e))?;
+    for result in rdr.deserialize() {
+        let record: Row = result.map_err(|e| Error::csv(path, e))?;
+        let age: u32 = record.age;
+        let count: u64 = record.count;
+        if let Some(filter) = filter
+            && age > filter
+        {
+            continue;
+        }
+        println!("Coin age {age}, number of occurrences {count}");
+        map.insert(age, count);
+    }
+    Ok(map)
+}
+
+fn plot_age_count(
+    data: BTreeMap<u32, u64>,
+    path: &Path,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let root = BitMapBackend::new(path, (1024, 768)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let max_age = *data.keys().max().unwrap_or(&1);
+    let max_count = *data.values().max().unwrap_or(&1);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption("UTXO Age Distribution", ("sans-serif", 30))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(80)
+        .build_cartesian_2d(0u32..max_age, (0u64..max_count * 2).log_scale())?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Age (blocks)")
+        .y_desc("Count")
+        .y_label_formatter(&|y| {
+            if *y >= 1_000_000 {
+                format!("{}M", y / 1_000_000)
+            } else if *y >= 1_000 {
+                format!("{}K", y / 1_000)
+            } else {
+                format!("{}", y)
+            }
+        })
+        .draw()?;
+
+    chart.draw_series(
+        data.iter()
+            .map(|(&age, &count)| Circle::new((age, count.max(1)), 3, BLUE.filled())),
+    )?;
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::BTreeMap,
+    f64::consts::LN_2,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use hintsfile::{EliasFano, Hintsfile};
+use kernel::ChainType;
+use serde::Serialize;
+use statrs::function::gamma::ln_gamma;
+use swiftsync_research::{
+    BlockSource, Chainstate, Config, Error, Report, Result, ScanArgs, bitmap, check_round_trip,
+    compact_size, golomb, hybrid, network_arg, network_name,
+};
+
+fn min_bits_permutation(m: u32, n: u32) -> f64 {
+    assert!(n <= m, "n must be <= m");
+    if n == 0 || n == m {
+        return 0.0;
+    }
+
+    let log2_perm =
+        ln_gamma((m + 1) as f64) - ln_gamma((n + 1) as f64) - ln_gamma((m - n + 1) as f64);
+
+    log2_perm / LN_2
+}
+
+fn size_run_lengths_compact_size(elements: &[u32]) -> usize {
+    let mut size = compact_size(elements.len() as u64);
+    assert!(elements.is_sorted());
+    let mut prev = 0;
+    for &element in elements {
+        let diff = element - prev;
+        size += compact_size(diff as u64);
+        prev = element;
+    }
+    size
+}
+
+fn size_run_lengths_varint(elements: &[u32]) -> usize {
+    let mut size = swiftsync_research::size_varint(elements.len() as u64);
+    assert!(elements.is_sorted());
+    let mut prev = 0;
+    for &element in elements {
+        let diff = element - prev;
+        size += swiftsync_research::size_varint(diff as u64);
+        prev = element;
+    }
+    size
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Compare the size of encodings of the unspent output indices in a hints file.
+    Compare {
+        #[command(flatten)]
+        hints: HintsArgs,
+        /// The network of the hints file, recorded in the JSON report [env: NETWORK]
+        #[arg(long, value_parser = network_arg)]
+        network: Option<ChainType>,
+        /// Also write the exact sizes as JSON to this file
+        #[arg(long, value_name = "FILE")]
+        json: Option<PathBuf>,
+    },
+    /// Write a hints file of the outputs unspent at the stop height from a data directory.
+    Generate {
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Where to write the hints file
+        #[arg(long, value_name = "FILE", default_value = "bitcoin.hints")]
+        output: PathBuf,
+    },
+    /// Check a hints file against the outputs unspent at its stop height in a data directory.
+    Verify {
+        #[command(flatten)]
+        hints: HintsArgs,
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Where to write every mismatched hint
+        #[arg(long, value_name = "FILE", default_value = "hint_mismatches.csv")]
+        output: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct HintsArgs {
+    /// Path to the hints file [env: HINTS_FILE]
+    #[arg(long, value_name = "FILE")]
+    hints: Option<PathBuf>,
+}
+
+impl HintsArgs {
+    fn path(&self) -> Result<PathBuf> {
+        self.hints
+            .clone()
+            .or_else(|| std::env::var_os("HINTS_FILE").map(PathBuf::from))
+            .ok_or_else(|| {
+                Error::Config("no hints file, set `HINTS_FILE` or pass `--hints`".into())
+            })
+    }
+}
+
+impl Command {
+    pub fn run(self) -> Result<()> {
+        match self {
+            Self::Compare {
+                hints,
+                network,
+                json,
+            } => {
+                let network = match (network, std::env::var("NETWORK")) {
+                    (Some(network), _) => network,
+                    (None, Ok(name)) => network_arg(&name).map_err(Error::Config)?,
+                    (None, Err(_)) => ChainType::Mainnet,
+                };
+                compare(&hints.path()?, network, json.as_deref())
+            }
+            Self::Generate { scan, output } => generate(&scan, &output),
+            Self::Verify {
+                hints,
+                scan,
+                output,
+            } => verify(&hints.path()?, &scan, &output),
+        }
+    }
+}
+
+/// Open the data directory of `config` for `command`, which must not select a spent-coin cache.
+fn open_chainstate(config: &Config, command: &str) -> Result<Chainstate> {
+    config.require_sequential(command)?;
+    if config.cache.is_some() {
+        return Err(Error::Config(
+            "hints are computed from blocks, a spent-coin cache does not hold them".into(),
+        ));
+    }
+    if config.range.start > 1 {
+        return Err(Error::Config("hints files always begin at height 1".into()));
+    }
+    let data_dir = config.network_dir().ok_or_else(|| {
+        Error::Config("no data directory, set `BITCOIN_DIR` or pass `--datadir`".into())
+    })?;
+    Chainstate::open(&data_dir, config.network)
+}
+
+fn read_hints(path: &Path) -> Result<Hintsfile> {
+    let mut file = File::open(path).map_err(|e| Error::io(path, e))?;
+    Hintsfile::from_reader(&mut file).map_err(|source| Error::Hintsfile {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn generate(scan: &ScanArgs, output: &Path) -> Result<()> {
+    let config = scan.config()?;
+    let chainstate = open_chainstate(&config, "hints generate")?;
+    let stop = config.range.stop.unwrap_or_else(|| chainstate.tip());
+    let hints = chainstate.hints(stop)?;
+    let file = File::create(output).map_err(|e| Error::io(output, e))?;
+    hints
+        .write(BufWriter::new(file))
+        .map_err(|e| Error::io(output, e))?;
+    println!(">>>");
+    println!(
+        "Wrote hints for {} unspent outputs up to height {stop} to {}",
+        hints.unspent_count(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn verify(path: &Path, scan: &ScanArgs, output: &Path) -> Result<()> {
+    println!("Using hintsfile {}", path.display());
+    let file = read_hints(path)?;
+    let stop = file.stop_height();
+    let mut config = scan.config()?;
+    // The file decides where to stop, overriding the mainnet default.
+    if scan.stop.is_some_and(|height| height != stop) {
+        return Err(Error::Config(format!(
+            "the hints file stops at height {stop}, it cannot be verified at another height"
+        )));
+    }
+    config.range.stop = Some(stop);
+    let hints = open_chainstate(&config, "hints verify")?.hints(stop)?;
+    let mismatches = hints.verify(&file)?;
+    let mut wtr = csv::Writer::from_path(output).map_err(|e| Error::csv(output, e))?;
+    wtr.write_record(["height", "index", "kind"])
+        .map_err(|e| Error::csv(output, e))?;
+    for mismatch in &mismatches {
+        wtr.write_record([
+            mismatch.height().to_string(),
+            mismatch.index().to_string(),
+            mismatch.kind().to_string(),
+        ])
+        .map_err(|e| Error::csv(output, e))?;
+    }
+    wtr.flush().map_err(|e| Error::io(output, e))?;
+    println!(">>>");
+    for kind in ["missing", "extra", "out_of_range"] {
+        let count = mismatches.iter().filter(|m| m.kind() == kind).count();
+        println!("{kind}: {count}");
+    }
+    if let Some(first) = mismatches.first() {
+        println!("First mismatch: {first}");
+        println!("Wrote mismatched hints to {}", output.display());
+        return Err(Error::WrongHints {
+            path: path.to_path_buf(),
+            stop,
+            mismatches: mismatches.len(),
+        });
+    }
+    println!(
+        "The hints of {} unspent outputs are exactly correct at height {stop}",
+        hints.unspent_count()
+    );
+    Ok(())
+}
+
+/// The total size in bytes of each encoding of the unspent output indices of a hints file.
+#[derive(Debug, Default, Serialize)]
+struct Comparison {
+    theoretic_minimum_bytes: f64,
+    elias_fano_bytes: usize,
+    adaptive_bytes: usize,
+    /// The number of blocks each encoding is the smallest adaptive choice for.
+    adaptive_wins: BTreeMap<&'static str, u32>,
+    golomb_rice_bytes: usize,
+    bitmap_bytes: usize,
+    run_length_bitmap_bytes: usize,
+    roaring_bytes: usize,
+    compact_size_run_lengths_bytes: usize,
+    varint_run_lengths_bytes: usize,
+    literal_bytes: usize,
+}
+
+fn compare(path: &Path, network: ChainType, json: Option<&Path>) -> Result<()> {
+    println!("Using hintsfile {}", path.display());
+    print!("Generating statistics");
+    let hints = read_hints(path)?;
+    let stop = hints.stop_height();
+    let mut sizes = Comparison {
+        adaptive_wins: hybrid::Encoding::ALL
+            .into_iter()
+            .map(|encoding| (encoding.name(), 0))
+            .collect(),
+        ..Default::default()
+    };
+    for height in 1..=stop {
+        let indices = hints
+            .indices_at_height(height)
+            .ok_or(Error::MissingHints { height })?;
+        let ef = EliasFano::compress(&indices);
+        sizes.elias_fano_bytes += ef.approximate_size();
+        sizes.literal_bytes += compact_size(indices.len() as u64) + 2 * indices.len();
+        sizes.compact_size_run_lengths_bytes += size_run_lengths_compact_size(&indices);
+        sizes.varint_run_lengths_bytes += size_run_lengths_varint(&indices);
+        let golomb_rice = golomb::encode(&indices);
+        check_round_trip(
+            "Golomb-Rice",
+            height,
+            &indices,
+            &golomb_rice,
+            golomb::decode,
+        )?;
+        sizes.golomb_rice_bytes += golomb_rice.len();
+        let raw = bitmap::encode(&indices);
+        check_round_trip("bitmap", height, &indices, &raw, bitmap::decode)?;
+        sizes.bitmap_bytes += raw.len();
+        let runs = bitmap::encode_runs(&indices);
+        check_round_trip(
+            "run-length bitmap",
+            height,
+            &indices,
+            &runs,
+            bitmap::decode_runs,
+        )?;
+        sizes.run_length_bitmap_bytes += runs.len();
+        let roaring = bitmap::encode_roaring(&indices);
+        check_round_trip(
+            "Roaring",
+            height,
+            &indices,
+            &roaring,
+            bitmap::decode_roaring,
+        )?;
+        sizes.roaring_bytes += roaring.len();
+        let (encoding, adaptive) = hybrid::encode(&indices);
+        check_round_trip("adaptive", height, &indices, &adaptive, hybrid::decode)?;
+        sizes.adaptive_bytes += adaptive.len();
+        *sizes.adaptive_wins.entry(encoding.name()).or_default() += 1;
+        let n = indices.len() as u32;
+        let m = indices.iter().max().copied().unwrap_or_default() + 1;
+        sizes.theoretic_minimum_bytes += min_bits_permutation(m, n) / 8.0;
+        if height % 10_000 == 0 {
+            println!("({height}/{stop})");
+        }
+    }
+    println!(">>>");
+    let mb = |bytes: usize| bytes as f64 / 1_000_000.;
+    println!(
+        "Theoretic minimum encoding {:<4} MB",
+        sizes.theoretic_minimum_bytes / 1_000_000.
+    );
+    println!(
+        "Size of Elias-Fano encoding {:<4} MB",
+        mb(sizes.elias_fano_bytes)
+    );
+    println!(
+        "Size of adaptive per-block encoding {:<4} MB",
+        mb(sizes.adaptive_bytes)
+    );
+    for (name, wins) in &sizes.adaptive_wins {
+        println!("  {name}: smallest for {wins} blocks");
+    }
+    println!(
+        "Size of Golomb-Rice encoded gaps {:<4} MB",
+        mb(sizes.golomb_rice_bytes)
+    );
+    println!("Size of bitmap encoding {:<4} MB", mb(sizes.bitmap_bytes));
+    println!(
+        "Size of run-length encoded bitmap {:<4} MB",
+        mb(sizes.run_length_bitmap_bytes)
+    );
+    println!(
+        "Size of Roaring-style bitmap {:<4} MB",
+        mb(sizes.roaring_bytes)
+    );
+    println!(
+        "Size of CompactSize encoded run-lengths {:<4} MB",
+        mb(sizes.compact_size_run_lengths_bytes)
+    );
+    println!(
+        "Size of VarInt encoded run-lengths {:<4} MB",
+        mb(sizes.varint_run_lengths_bytes)
+    );
+    println!(
+        "Size of encoding indices literally {:<4} MB",
+        mb(sizes.literal_bytes)
+    );
+    match json {
+        Some(json) => Report {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            network: network_name(network).to_string(),
+            start_height: 1,
+            stop_height: stop,
+            results: sizes,
+        }
+        .write_json(json),
+        None => Ok(()),
+    }
+}
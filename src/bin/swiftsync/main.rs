@@ -0,0 +1,217 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use swiftsync_research::{
+    CacheWriter, ChainWalker, Error, Merge, Result, ScanArgs,
+    analysis::{
+        AmountSavings, Analyses, Analysis, BareMultisig, CoinAges, Entropy, EntropyCoding,
+        HeightEncodings, ScriptSavings, ScriptTypes, UncompressedP2pk,
+    },
+    verify_block_inputs,
+};
+
+mod ages;
+mod hints;
+
+/// Measure the data a SwiftSync client downloads and stores.
+#[derive(Parser)]
+#[command(name = "swiftsync", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Analyze hints files.
+    #[command(subcommand)]
+    Hints(hints::Command),
+    /// Show the savings due to the amount compression format.
+    Amounts(AnalysisArgs),
+    /// Show the savings due to the `ReconstructableScript` format.
+    Scripts(AnalysisArgs),
+    /// Count P2PK outputs that are uncompressed, and how many of their keys compress.
+    P2pk(AnalysisArgs),
+    /// Count bare multisig outputs and the savings of compressing them.
+    Multisig(AnalysisArgs),
+    /// Break spent coins and their script sizes down by script type.
+    ScriptTypes(AnalysisArgs),
+    /// Compare encodings of the confirmation heights of spent coins.
+    Heights(AnalysisArgs),
+    /// Compare range coded amounts, ages and script tags with their byte-aligned encodings.
+    EntropyCoding(AnalysisArgs),
+    /// Measure the empirical entropy of amounts, ages, script types and script lengths.
+    Entropy(AnalysisArgs),
+    /// Analyze the ages of spent coins.
+    #[command(subcommand)]
+    Ages(ages::Command),
+    /// Run every spent-coin analysis in a single pass over the chain.
+    All {
+        #[command(flatten)]
+        args: AnalysisArgs,
+        /// Count the ages of coinbase outputs as well.
+        #[arg(long)]
+        include_coinbase: bool,
+        /// Directory to write the coin age tables to.
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Serialize the spent coins of each block as SwiftSync inputs, check that they deserialize
+    /// back to the same coins and write the serialized size of each block to a CSV table.
+    VerifyInputs {
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Path of the table to write.
+        #[arg(long, value_name = "FILE", default_value = "block_inputs.csv")]
+        output: PathBuf,
+        /// Also write the totals as JSON to this file.
+        #[arg(long, value_name = "FILE")]
+        json: Option<PathBuf>,
+    },
+    /// Extract the spent coins of each block into a cache file.
+    ExtractCache {
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Path of the cache file to write.
+        #[arg(long, value_name = "FILE", default_value = "spent_coins.cache")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct AnalysisArgs {
+    #[command(flatten)]
+    scan: ScanArgs,
+    /// Also write the exact results as JSON to this file.
+    #[arg(long, value_name = "FILE")]
+    json: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    swiftsync_research::exit(run(Cli::parse()))
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let here = Path::new(".");
+    match cli.command {
+        Command::Hints(command) => command.run(),
+        Command::Amounts(args) => analyze(&args, here, AmountSavings::default),
+        Command::Scripts(args) => analyze(&args, here, ScriptSavings::default),
+        Command::P2pk(args) => analyze(&args, here, UncompressedP2pk::default),
+        Command::Multisig(args) => analyze(&args, here, BareMultisig::default),
+        Command::ScriptTypes(args) => analyze(&args, here, ScriptTypes::default),
+        Command::Heights(args) => analyze(&args, here, HeightEncodings::default),
+        Command::EntropyCoding(args) => analyze(&args, here, EntropyCoding::default),
+        Command::Entropy(args) => analyze(&args, here, Entropy::default),
+        Command::Ages(command) => command.run(),
+        Command::All {
+            args,
+            include_coinbase,
+            dir,
+        } => analyze(&args, &dir, || {
+            let mut analyses = Analyses::new();
+            analyses.register(AmountSavings::default());
+            analyses.register(ScriptSavings::default());
+            analyses.register(UncompressedP2pk::default());
+            analyses.register(BareMultisig::default());
+            analyses.register(ScriptTypes::default());
+            analyses.register(HeightEncodings::default());
+            analyses.register(EntropyCoding::default());
+            analyses.register(Entropy::default());
+            analyses.register(CoinAges::new(include_coinbase));
+            analyses
+        }),
+        Command::VerifyInputs { scan, output, json } => {
+            verify_inputs(&scan, &output, json.as_deref())
+        }
+        Command::ExtractCache { scan, output } => {
+            let config = scan.config()?;
+            config.require_sequential("extract-cache")?;
+            let walker = ChainWalker::new(&config)?;
+            let mut writer = CacheWriter::create(&output, walker.network())
+                .map_err(|e| Error::io(&output, e))?;
+            walker.walk(&mut writer)?;
+            writer.finish().map_err(|e| Error::io(&output, e))?;
+            println!("Wrote spent coins to {}", output.display());
+            Ok(())
+        }
+    }
+}
+
+/// Scan the chain with the analysis built by `make` and report its results, writing any tables
+/// to `dir`.
+fn analyze<A>(args: &AnalysisArgs, dir: &Path, make: impl Fn() -> A + Sync) -> Result<()>
+where
+    A: Analysis + Merge,
+{
+    let walker = ChainWalker::new(&args.scan.config()?)?;
+    let analysis = walker.scan(make)?;
+    println!(">>>");
+    analysis.report(dir)?;
+    match &args.json {
+        Some(path) => walker.report(analysis.results()).write_json(path),
+        None => Ok(()),
+    }
+}
+
+/// Round-trip the inputs of every block in the configured range, stopping at the first block
+/// whose coins do not survive it.
+fn verify_inputs(scan: &ScanArgs, output: &Path, json: Option<&Path>) -> Result<()> {
+    let config = scan.config()?;
+    config.require_sequential("verify-inputs")?;
+    let walker = ChainWalker::new(&config)?;
+    let range = walker.range();
+    let mut wtr = csv::Writer::from_path(output).map_err(|e| Error::csv(output, e))?;
+    wtr.write_record(["block", "coins", "bytes"])
+        .map_err(|e| Error::csv(output, e))?;
+    let mut totals = InputSizes::default();
+    for block in walker.blocks() {
+        let block = block?;
+        if range.is_past(block.height) {
+            break;
+        }
+        if !range.contains(block.height) {
+            continue;
+        }
+        let size = verify_block_inputs(&block)?;
+        wtr.write_record([
+            block.height.to_string(),
+            block.coins.len().to_string(),
+            size.to_string(),
+        ])
+        .map_err(|e| Error::csv(output, e))?;
+        totals.blocks += 1;
+        totals.coins += block.coins.len() as u64;
+        totals.bytes += size as u64;
+    }
+    let InputSizes {
+        blocks,
+        coins,
+        bytes,
+    } = totals;
+    wtr.flush().map_err(|e| Error::io(output, e))?;
+    println!(">>>");
+    println!("Verified the inputs of {blocks} blocks spending {coins} coins");
+    println!(
+        "Serialized size: {:.2}MB ({bytes} bytes), {:.1} bytes per coin",
+        bytes as f64 / 1_000_000.,
+        bytes as f64 / coins.max(1) as f64
+    );
+    println!("Wrote block sizes to {}", output.display());
+    match json {
+        Some(path) => walker.report(totals).write_json(path),
+        None => Ok(()),
+    }
+}
+
+/// The blocks and coins whose inputs were verified, and their serialized size in bytes.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+struct InputSizes {
+    blocks: u64,
+    coins: u64,
+    bytes: u64,
+}
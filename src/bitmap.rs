@@ -0,0 +1,209 @@
+//! Bitmap encodings of strictly increasing indices, such as the unspent outputs of a block.
+//!
+//! Every encoding covers the bits from zero through the largest index, so a bit is set for each
+//! index and the last bit is always set.
+//!
+//! - [`encode`]: the `CompactSize` number of bits, then the bits, least significant first.
+//! - [`encode_runs`]: the `CompactSize` number of runs, then the length of each as a `VarInt`,
+//!   alternating between unset and set bits and starting with a possibly empty run of unset bits.
+//! - [`encode_roaring`]: containers of the indices sharing their upper 16 bits, as in Roaring
+//!   bitmaps. Each is the smallest of a sorted array of the lower 16 bits, a bitmap of all 2^16
+//!   bits, or runs of set bits.
+
+use std::io;
+
+use crate::{
+    compact_size,
+    encoding::{check_increasing, invalid_data, is_increasing},
+    read_compact_size, read_varint, write_compact_size, write_varint,
+};
+
+const CONTAINER_BITS: u32 = 16;
+const CONTAINER_BYTES: usize = (1 << CONTAINER_BITS) / 8;
+
+const ARRAY: u8 = 0;
+const BITMAP: u8 = 1;
+const RUNS: u8 = 2;
+
+/// Serialize `indices` as a plain bitmap.
+pub fn encode(indices: &[u32]) -> Vec<u8> {
+    check_increasing(indices);
+    let bits = indices.last().map_or(0, |&last| last as u64 + 1);
+    let mut out = Vec::new();
+    write_compact_size(&mut out, bits).expect("writing to a vector");
+    let start = out.len();
+    out.resize(start + bits.div_ceil(8) as usize, 0);
+    for &index in indices {
+        out[start + index as usize / 8] |= 1 << (index % 8);
+    }
+    out
+}
+
+/// Deserialize a bitmap written by [`encode`], returning the indices and the number of bytes read.
+pub fn decode(bytes: &[u8]) -> io::Result<(Vec<u32>, usize)> {
+    let mut reader = bytes;
+    let bits = read_compact_size(&mut reader, false)?;
+    if bits > u32::MAX as u64 + 1 {
+        return Err(invalid_data("bitmap is too long"));
+    }
+    let len = bits.div_ceil(8) as usize;
+    let bitmap = reader.get(..len).ok_or(io::ErrorKind::UnexpectedEof)?;
+    let indices = (0..bits)
+        .filter(|&i| bitmap[i as usize / 8] >> (i % 8) & 1 == 1)
+        .map(|i| i as u32)
+        .collect();
+    Ok((indices, bytes.len() - reader.len() + len))
+}
+
+/// The lengths of the alternating runs of unset and set bits of the bitmap of `indices`.
+fn runs(indices: &[u32]) -> Vec<u64> {
+    check_increasing(indices);
+    let mut runs = Vec::new();
+    let mut next = 0u64;
+    for &index in indices {
+        let index = index as u64;
+        if index == next && !runs.is_empty() {
+            *runs.last_mut().expect("runs is not empty") += 1;
+        } else {
+            runs.push(index - next);
+            runs.push(1);
+        }
+        next = index + 1;
+    }
+    runs
+}
+
+/// Serialize `indices` as a run-length encoded bitmap.
+pub fn encode_runs(indices: &[u32]) -> Vec<u8> {
+    let runs = runs(indices);
+    let mut out = Vec::new();
+    write_compact_size(&mut out, runs.len() as u64).expect("writing to a vector");
+    for run in runs {
+        write_varint(&mut out, run).expect("writing to a vector");
+    }
+    out
+}
+
+/// Deserialize runs written by [`encode_runs`], returning the indices and the number of bytes
+/// read.
+pub fn decode_runs(bytes: &[u8]) -> io::Result<(Vec<u32>, usize)> {
+    let mut reader = bytes;
+    let count = read_compact_size(&mut reader, true)?;
+    let mut indices = Vec::new();
+    let mut next = 0u64;
+    for i in 0..count {
+        let run = read_varint(&mut reader)?;
+        let end = next
+            .checked_add(run)
+            .filter(|&end| end <= u32::MAX as u64 + 1)
+            .ok_or_else(|| invalid_data("run passes the largest index"))?;
+        if i % 2 == 1 {
+            indices.extend((next..end).map(|index| index as u32));
+        }
+        next = end;
+    }
+    Ok((indices, bytes.len() - reader.len()))
+}
+
+/// Serialize `indices` as Roaring-style containers.
+pub fn encode_roaring(indices: &[u32]) -> Vec<u8> {
+    check_increasing(indices);
+    let containers: Vec<_> = indices
+        .chunk_by(|a, b| a >> CONTAINER_BITS == b >> CONTAINER_BITS)
+        .collect();
+    let mut out = Vec::new();
+    write_compact_size(&mut out, containers.len() as u64).expect("writing to a vector");
+    for container in containers {
+        let key = (container[0] >> CONTAINER_BITS) as u16;
+        out.extend_from_slice(&key.to_le_bytes());
+        let lows: Vec<u16> = container.iter().map(|&index| index as u16).collect();
+        let runs = container_runs(&lows);
+        let array_len = compact_size(lows.len() as u64) + 2 * lows.len();
+        let runs_len = compact_size(runs.len() as u64) + 4 * runs.len();
+        if runs_len < array_len.min(CONTAINER_BYTES) {
+            out.push(RUNS);
+            write_compact_size(&mut out, runs.len() as u64).expect("writing to a vector");
+            for (start, len) in runs {
+                out.extend_from_slice(&start.to_le_bytes());
+                out.extend_from_slice(&((len - 1) as u16).to_le_bytes());
+            }
+        } else if array_len <= CONTAINER_BYTES {
+            out.push(ARRAY);
+            write_compact_size(&mut out, lows.len() as u64).expect("writing to a vector");
+            for low in lows {
+                out.extend_from_slice(&low.to_le_bytes());
+            }
+        } else {
+            out.push(BITMAP);
+            let start = out.len();
+            out.resize(start + CONTAINER_BYTES, 0);
+            for low in lows {
+                out[start + low as usize / 8] |= 1 << (low % 8);
+            }
+        }
+    }
+    out
+}
+
+/// The runs of consecutive values in `lows` as their start and length.
+fn container_runs(lows: &[u16]) -> Vec<(u16, u32)> {
+    let mut runs: Vec<(u16, u32)> = Vec::new();
+    for &low in lows {
+        match runs.last_mut() {
+            Some((start, len)) if *start as u32 + *len == low as u32 => *len += 1,
+            _ => runs.push((low, 1)),
+        }
+    }
+    runs
+}
+
+/// Deserialize containers written by [`encode_roaring`], returning the indices and the number of
+/// bytes read.
+pub fn decode_roaring(bytes: &[u8]) -> io::Result<(Vec<u32>, usize)> {
+    let mut reader = bytes;
+    let count = read_compact_size(&mut reader, true)?;
+    let mut indices = Vec::new();
+    for _ in 0..count {
+        let key = u16::from_le_bytes(read_array(&mut reader)?) as u32;
+        let base = key << CONTAINER_BITS;
+        let [kind] = read_array(&mut reader)?;
+        match kind {
+            ARRAY => {
+                let len = read_compact_size(&mut reader, true)?;
+                for _ in 0..len {
+                    indices.push(base | u16::from_le_bytes(read_array(&mut reader)?) as u32);
+                }
+            }
+            BITMAP => {
+                let bitmap: [u8; CONTAINER_BYTES] = read_array(&mut reader)?;
+                indices.extend(
+                    (0..1u32 << CONTAINER_BITS)
+                        .filter(|&low| bitmap[low as usize / 8] >> (low % 8) & 1 == 1)
+                        .map(|low| base | low),
+                );
+            }
+            RUNS => {
+                let len = read_compact_size(&mut reader, true)?;
+                for _ in 0..len {
+                    let start = u16::from_le_bytes(read_array(&mut reader)?) as u32;
+                    let last = start + u16::from_le_bytes(read_array(&mut reader)?) as u32;
+                    if last >> CONTAINER_BITS != 0 {
+                        return Err(invalid_data("run passes the end of its container"));
+                    }
+                    indices.extend((start..=last).map(|low| base | low));
+                }
+            }
+            _ => return Err(invalid_data("unknown container type")),
+        }
+    }
+    if !is_increasing(&indices) {
+        return Err(invalid_data("indices are not strictly increasing"));
+    }
+    Ok((indices, bytes.len() - reader.len()))
+}
+
+fn read_array<const N: usize>(reader: &mut &[u8]) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    io::Read::read_exact(reader, &mut buf)?;
+    Ok(buf)
+}
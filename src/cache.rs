@@ -0,0 +1,365 @@
+//! A compact, columnar file of the spent coins of each block, extracted from undo data so
+//! analyses may be rerun without a Bitcoin Core data directory.
+//!
+//! Layout, integers are little endian unless marked as VarInt:
+//!
+//! ```text
+//! header  | magic "SSCC" | version u8 | network u8 |
+//! block   | height VarInt | coin count VarInt | ages VarInt.. | coinbase bitmap |
+//!         | values VarInt.. | script lengths VarInt.. | script bytes.. |
+//! index   | (height u32, offset u64).. |
+//! footer  | block count u32 | index offset u64 | magic "SSCC" |
+//! ```
+//!
+//! The age of a coin is the height of the spending block minus its confirmation height.
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use kernel::ChainType;
+
+use crate::{
+    BlockSource, Error, Result, SpentBlock, SpentCoin, SpentCoinVisitor, encoding::invalid_data,
+    read_varint, write_varint,
+};
+
+const MAGIC: [u8; 4] = *b"SSCC";
+const VERSION: u8 = 0x01;
+const HEADER_LEN: u64 = 6;
+const FOOTER_LEN: u64 = 16;
+const INDEX_ENTRY_LEN: u64 = 12;
+
+fn network_to_byte(network: ChainType) -> u8 {
+    match network {
+        ChainType::Mainnet => 0,
+        ChainType::Testnet => 1,
+        ChainType::Testnet4 => 2,
+        ChainType::Signet => 3,
+        ChainType::Regtest => 4,
+    }
+}
+
+fn network_from_byte(byte: u8) -> io::Result<ChainType> {
+    match byte {
+        0 => Ok(ChainType::Mainnet),
+        1 => Ok(ChainType::Testnet),
+        2 => Ok(ChainType::Testnet4),
+        3 => Ok(ChainType::Signet),
+        4 => Ok(ChainType::Regtest),
+        _ => Err(invalid_data(format!("unknown network {byte}"))),
+    }
+}
+
+/// Writes blocks of spent coins to a cache. As a [`SpentCoinVisitor`], every visited block is
+/// written in order.
+pub struct CacheWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    index: Vec<(u32, u64)>,
+    pending: Option<SpentBlock>,
+    error: Option<io::Error>,
+}
+
+impl CacheWriter<BufWriter<File>> {
+    pub fn create(path: &Path, network: ChainType) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), network)
+    }
+}
+
+impl<W: Write> CacheWriter<W> {
+    pub fn new(mut writer: W, network: ChainType) -> io::Result<Self> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION, network_to_byte(network)])?;
+        Ok(Self {
+            writer,
+            offset: HEADER_LEN,
+            index: Vec::new(),
+            pending: None,
+            error: None,
+        })
+    }
+
+    /// Append a block. Blocks must be written in strictly ascending height order.
+    pub fn write_block(&mut self, block: &SpentBlock) -> io::Result<()> {
+        if let Some(&(last, _)) = self.index.last()
+            && block.height <= last
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("block {} written after block {last}", block.height),
+            ));
+        }
+        let mut buf = Vec::new();
+        write_varint(&mut buf, block.height as u64)?;
+        write_varint(&mut buf, block.coins.len() as u64)?;
+        for coin in &block.coins {
+            let age = block
+                .height
+                .checked_sub(coin.confirmation_height)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "coin confirmed after the spending block",
+                    )
+                })?;
+            write_varint(&mut buf, age as u64)?;
+        }
+        let mut bitmap = vec![0u8; block.coins.len().div_ceil(8)];
+        for (i, coin) in block.coins.iter().enumerate() {
+            if coin.is_coinbase {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        buf.extend(bitmap);
+        for coin in &block.coins {
+            write_varint(&mut buf, coin.value as u64)?;
+        }
+        for coin in &block.coins {
+            write_varint(&mut buf, coin.script_pubkey.len() as u64)?;
+        }
+        for coin in &block.coins {
+            buf.extend_from_slice(&coin.script_pubkey);
+        }
+        self.writer.write_all(&buf)?;
+        self.index.push((block.height, self.offset));
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Write the pending block and the index, returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_pending();
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let index_offset = self.offset;
+        for (height, offset) in &self.index {
+            self.writer.write_all(&height.to_le_bytes())?;
+            self.writer.write_all(&offset.to_le_bytes())?;
+        }
+        self.writer
+            .write_all(&(self.index.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.write_all(&MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush_pending(&mut self) {
+        if let Some(block) = self.pending.take()
+            && self.error.is_none()
+            && let Err(e) = self.write_block(&block)
+        {
+            self.error = Some(e);
+        }
+    }
+}
+
+impl<W: Write> SpentCoinVisitor for CacheWriter<W> {
+    fn visit_block(&mut self, height: u32) {
+        self.flush_pending();
+        self.pending = Some(SpentBlock {
+            height,
+            coins: Vec::new(),
+        });
+    }
+
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        if let Some(block) = self.pending.as_mut() {
+            block.coins.push(coin.clone());
+        }
+    }
+}
+
+/// Reads a cache written by [`CacheWriter`].
+pub struct CacheReader {
+    path: PathBuf,
+    network: ChainType,
+    index: Vec<(u32, u64)>,
+    /// Where the blocks end.
+    index_offset: u64,
+}
+
+impl CacheReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::read_index(path).map_err(|e| Error::io(path, e))
+    }
+
+    fn read_index(path: &Path) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(invalid_data("not a spent-coin cache"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported cache version {}",
+                header[4]
+            )));
+        }
+        let network = network_from_byte(header[5])?;
+        let file_len = file.seek(SeekFrom::End(-(FOOTER_LEN as i64)))? + FOOTER_LEN;
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        file.read_exact(&mut footer)?;
+        if footer[12..] != MAGIC {
+            return Err(invalid_data("cache is truncated"));
+        }
+        let count = u32::from_le_bytes(footer[..4].try_into().unwrap());
+        let index_offset = u64::from_le_bytes(footer[4..12].try_into().unwrap());
+        // The index fills the file between the blocks and the footer, checked before allocating.
+        let index_len = count as u64 * INDEX_ENTRY_LEN;
+        if index_offset < HEADER_LEN
+            || index_offset.checked_add(index_len + FOOTER_LEN) != Some(file_len)
+        {
+            return Err(invalid_data("cache index does not fit the file"));
+        }
+        file.seek(SeekFrom::Start(index_offset))?;
+        let mut entries = vec![0u8; index_len as usize];
+        file.read_exact(&mut entries)?;
+        let index = entries
+            .chunks_exact(INDEX_ENTRY_LEN as usize)
+            .map(|entry| {
+                let height = u32::from_le_bytes(entry[..4].try_into().unwrap());
+                let offset = u64::from_le_bytes(entry[4..].try_into().unwrap());
+                (height, offset)
+            })
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            network,
+            index,
+            index_offset,
+        })
+    }
+
+    /// The heights of the first and last block in the cache.
+    pub fn heights(&self) -> Option<(u32, u32)> {
+        Some((self.index.first()?.0, self.index.last()?.0))
+    }
+
+    fn read_blocks_from(&self, start: u32) -> io::Result<CacheBlocks<'_>> {
+        let position = self.index.partition_point(|&(height, _)| height < start);
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        if let Some(&(_, offset)) = self.index.get(position) {
+            reader.seek(SeekFrom::Start(offset))?;
+        }
+        Ok(CacheBlocks {
+            path: &self.path,
+            reader,
+            end: self.index_offset,
+            index: &self.index[position..],
+        })
+    }
+}
+
+impl BlockSource for CacheReader {
+    fn network(&self) -> ChainType {
+        self.network
+    }
+
+    fn tip(&self) -> u32 {
+        self.index.last().map_or(0, |&(height, _)| height)
+    }
+
+    fn blocks_from(&self, start: u32) -> Box<dyn Iterator<Item = Result<SpentBlock>> + '_> {
+        match self.read_blocks_from(start) {
+            Ok(blocks) => Box::new(blocks),
+            Err(e) => Box::new(std::iter::once(Err(Error::io(&self.path, e)))),
+        }
+    }
+}
+
+struct CacheBlocks<'a> {
+    path: &'a Path,
+    reader: BufReader<File>,
+    /// Where the blocks end.
+    end: u64,
+    /// The height and offset of each block left to read.
+    index: &'a [(u32, u64)],
+}
+
+impl CacheBlocks<'_> {
+    /// Fail unless `len` more bytes of blocks follow, so corrupt lengths cannot cause huge
+    /// allocations.
+    fn check_remaining(&mut self, len: u64, what: &str) -> io::Result<()> {
+        let position = self.reader.stream_position()?;
+        if len > self.end.saturating_sub(position) {
+            return Err(invalid_data(format!(
+                "{what} exceeds the blocks of the cache"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read the block the index places at `height` and `offset`.
+    fn read_block(&mut self, height: u32, offset: u64) -> io::Result<SpentBlock> {
+        if self.reader.stream_position()? != offset {
+            return Err(invalid_data(format!(
+                "block {height} does not start where the index points"
+            )));
+        }
+        let stored = read_varint(&mut self.reader)?;
+        if stored != height as u64 {
+            return Err(invalid_data(format!(
+                "block {stored} is indexed at height {height}"
+            )));
+        }
+        let n = read_varint(&mut self.reader)?;
+        // Every coin takes at least a byte for each of its age, value and script length.
+        self.check_remaining(n.saturating_mul(3), "coin count")?;
+        let n = n as usize;
+        let reader = &mut self.reader;
+        let mut ages = Vec::with_capacity(n);
+        for _ in 0..n {
+            ages.push(read_varint(reader)? as u32);
+        }
+        let mut bitmap = vec![0u8; n.div_ceil(8)];
+        reader.read_exact(&mut bitmap)?;
+        let mut values = Vec::with_capacity(n);
+        for _ in 0..n {
+            values.push(read_varint(reader)? as i64);
+        }
+        let mut lens = Vec::with_capacity(n);
+        for _ in 0..n {
+            lens.push(read_varint(reader)? as usize);
+        }
+        let script_bytes = lens
+            .iter()
+            .try_fold(0u64, |sum, &len| sum.checked_add(len as u64))
+            .unwrap_or(u64::MAX);
+        self.check_remaining(script_bytes, "script length")?;
+        let reader = &mut self.reader;
+        let mut coins = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut script_pubkey = vec![0u8; lens[i]];
+            reader.read_exact(&mut script_pubkey)?;
+            let confirmation_height = height
+                .checked_sub(ages[i])
+                .ok_or_else(|| invalid_data("coin age exceeds the block height"))?;
+            coins.push(SpentCoin {
+                confirmation_height,
+                is_coinbase: bitmap[i / 8] & (1 << (i % 8)) != 0,
+                value: values[i],
+                script_pubkey,
+            });
+        }
+        Ok(SpentBlock { height, coins })
+    }
+}
+
+impl Iterator for CacheBlocks<'_> {
+    type Item = Result<SpentBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&(height, offset), rest) = self.index.split_first()?;
+        self.index = rest;
+        let block = self.read_block(height, offset).map_err(|e| {
+            self.index = &[];
+            Error::io(self.path, e)
+        });
+        Some(block)
+    }
+}
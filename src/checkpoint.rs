@@ -0,0 +1,185 @@
+use std::{
+    ffi::OsString,
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{Error, Result, SpentCoin, SpentCoinVisitor};
+
+/// State that may be saved to, and restored from, a checkpoint file as JSON.
+pub trait Checkpoint {
+    fn save(&self, writer: &mut dyn Write) -> Result<(), serde_json::Error>;
+
+    /// Read the state written by [`save`](Self::save), leaving whatever follows it unread.
+    fn restore(&mut self, reader: &mut dyn Read) -> Result<(), serde_json::Error>;
+}
+
+impl<T: Serialize + DeserializeOwned> Checkpoint for T {
+    fn save(&self, writer: &mut dyn Write) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(writer, self)
+    }
+
+    fn restore(&mut self, reader: &mut dyn Read) -> Result<(), serde_json::Error> {
+        *self = T::deserialize(&mut serde_json::Deserializer::from_reader(reader))?;
+        Ok(())
+    }
+}
+
+/// What a checkpoint was saved for. Resuming another analysis, network or range from it would mix
+/// incompatible state into the results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointScope {
+    pub analysis: String,
+    pub network: String,
+    pub start_height: u32,
+    pub stop_height: Option<u32>,
+}
+
+impl fmt::Display for CheckpointScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on {} from height {}",
+            self.analysis, self.network, self.start_height
+        )?;
+        match self.stop_height {
+            Some(stop) => write!(f, " to {stop}"),
+            None => write!(f, " to the tip"),
+        }
+    }
+}
+
+/// Precedes the state in a checkpoint file.
+#[derive(Serialize, Deserialize)]
+struct CheckpointHeader {
+    height: u32,
+    #[serde(flatten)]
+    scope: CheckpointScope,
+}
+
+/// Write the `state` of a visitor that has processed every block up to and including `height`.
+/// The file is replaced atomically, so a crash mid-write leaves the previous checkpoint intact.
+pub fn save_checkpoint<C: Checkpoint + ?Sized>(
+    path: &Path,
+    scope: &CheckpointScope,
+    height: u32,
+    state: &C,
+) -> Result<()> {
+    let checkpoint_error = |source| Error::Checkpoint {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut tmp = OsString::from(path);
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let file = File::create(&tmp).map_err(|e| Error::io(&tmp, e))?;
+    let mut writer = BufWriter::new(file);
+    // The state is streamed rather than built up in memory, it can be far larger than the visitor.
+    let header = CheckpointHeader {
+        height,
+        scope: scope.clone(),
+    };
+    serde_json::to_writer(&mut writer, &header).map_err(checkpoint_error)?;
+    writer.write_all(b"\n").map_err(|e| Error::io(&tmp, e))?;
+    state.save(&mut writer).map_err(checkpoint_error)?;
+    writer.flush().map_err(|e| Error::io(&tmp, e))?;
+    std::fs::rename(&tmp, path).map_err(|e| Error::io(path, e))
+}
+
+/// Restore the `state` saved at `path` for `scope`, returning the last height it has processed.
+pub fn load_checkpoint<C: Checkpoint + ?Sized>(
+    path: &Path,
+    scope: &CheckpointScope,
+    state: &mut C,
+) -> Result<u32> {
+    let checkpoint_error = |source| Error::Checkpoint {
+        path: path.to_path_buf(),
+        source,
+    };
+    let file = File::open(path).map_err(|e| Error::io(path, e))?;
+    let mut reader = BufReader::new(file);
+    let header =
+        CheckpointHeader::deserialize(&mut serde_json::Deserializer::from_reader(&mut reader))
+            .map_err(checkpoint_error)?;
+    if header.scope != *scope {
+        return Err(checkpoint_error(serde::de::Error::custom(format!(
+            "saved for {}, not {scope}",
+            header.scope
+        ))));
+    }
+    state.restore(&mut reader).map_err(checkpoint_error)?;
+    let mut rest = Vec::new();
+    reader
+        .read_to_end(&mut rest)
+        .map_err(|e| Error::io(path, e))?;
+    if !rest.iter().all(u8::is_ascii_whitespace) {
+        return Err(checkpoint_error(serde::de::Error::custom(
+            "trailing data after the saved state",
+        )));
+    }
+    Ok(header.height)
+}
+
+/// Wraps a visitor to save its state every `interval` blocks.
+pub struct Checkpointed<'a, V: ?Sized> {
+    visitor: &'a mut V,
+    path: PathBuf,
+    scope: CheckpointScope,
+    interval: u32,
+    since_save: u32,
+    last: Option<u32>,
+    error: Option<Error>,
+}
+
+impl<'a, V: SpentCoinVisitor + Checkpoint + ?Sized> Checkpointed<'a, V> {
+    pub fn new(visitor: &'a mut V, path: PathBuf, scope: CheckpointScope, interval: u32) -> Self {
+        Self {
+            visitor,
+            path,
+            scope,
+            interval: interval.max(1),
+            since_save: 0,
+            last: None,
+            error: None,
+        }
+    }
+
+    /// Save the state after the last visited block. Returns the first error met while saving.
+    pub fn finish(self) -> Result<()> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        match self.last {
+            Some(height) => save_checkpoint(&self.path, &self.scope, height, self.visitor),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<V: SpentCoinVisitor + Checkpoint + ?Sized> SpentCoinVisitor for Checkpointed<'_, V> {
+    fn visit_block(&mut self, height: u32) {
+        // Every block before this one is complete.
+        if let Some(last) = self.last
+            && self.since_save >= self.interval
+            && self.error.is_none()
+        {
+            println!("Saving checkpoint at height {last}");
+            if let Err(e) = save_checkpoint(&self.path, &self.scope, last, self.visitor) {
+                eprintln!("warning: {e}, no further checkpoints will be saved");
+                self.error = Some(e);
+            }
+            self.since_save = 0;
+        }
+        self.since_save += 1;
+        self.last = Some(height);
+        self.visitor.visit_block(height);
+    }
+
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        self.visitor.visit_coin(height, coin);
+    }
+}
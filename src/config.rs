@@ -0,0 +1,272 @@
+use std::path::PathBuf;
+
+use kernel::ChainType;
+
+use crate::{Error, REFERENCE_HEIGHT, Result};
+
+/// An inclusive window of block heights. An open `stop` runs to the tip of the active chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightRange {
+    pub start: u32,
+    pub stop: Option<u32>,
+}
+
+impl HeightRange {
+    pub const fn new(start: u32, stop: Option<u32>) -> Self {
+        Self { start, stop }
+    }
+
+    pub fn contains(&self, height: u32) -> bool {
+        height >= self.start && self.stop.is_none_or(|stop| height <= stop)
+    }
+
+    /// Heights past the end of the window are never visited.
+    pub fn is_past(&self, height: u32) -> bool {
+        self.stop.is_some_and(|stop| height > stop)
+    }
+
+    /// Partition the window, clamped to a chain `tip`, into at most `parts` contiguous ranges
+    /// of near equal length, in ascending order.
+    pub fn split(&self, tip: u32, parts: usize) -> Vec<HeightRange> {
+        let end = self.stop.map_or(tip, |stop| stop.min(tip));
+        if self.start > end || parts == 0 {
+            return Vec::new();
+        }
+        let len = (end - self.start) as u64 + 1;
+        let chunk = len.div_ceil(parts as u64);
+        let mut ranges = Vec::new();
+        let mut start = self.start as u64;
+        while start <= end as u64 {
+            let stop = (start + chunk - 1).min(end as u64);
+            ranges.push(HeightRange::new(start as u32, Some(stop as u32)));
+            start = stop + 1;
+        }
+        ranges
+    }
+}
+
+impl Default for HeightRange {
+    fn default() -> Self {
+        Self::new(0, Some(REFERENCE_HEIGHT))
+    }
+}
+
+/// Parse a network name as used by Bitcoin Core's `-chain` option.
+pub fn parse_network(name: &str) -> Option<ChainType> {
+    match name {
+        "main" | "mainnet" | "bitcoin" => Some(ChainType::Mainnet),
+        "test" | "testnet" | "testnet3" => Some(ChainType::Testnet),
+        "testnet4" => Some(ChainType::Testnet4),
+        "signet" => Some(ChainType::Signet),
+        "regtest" => Some(ChainType::Regtest),
+        _ => None,
+    }
+}
+
+pub fn network_name(network: ChainType) -> &'static str {
+    match network {
+        ChainType::Mainnet => "mainnet",
+        ChainType::Testnet => "testnet3",
+        ChainType::Testnet4 => "testnet4",
+        ChainType::Signet => "signet",
+        ChainType::Regtest => "regtest",
+    }
+}
+
+/// Where and what to scan.
+///
+/// Without an explicit stop height, mainnet scans stop at the reference height and every other
+/// network runs to the tip.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub data_dir: Option<PathBuf>,
+    /// Read spent coins from this cache file instead of a data directory.
+    pub cache: Option<PathBuf>,
+    pub network: ChainType,
+    pub range: HeightRange,
+    pub threads: usize,
+    /// Periodically save the analysis state to this file.
+    pub checkpoint: Option<PathBuf>,
+    /// The number of blocks between checkpoints.
+    pub checkpoint_interval: u32,
+    /// Continue from the state saved in the checkpoint file.
+    pub resume: bool,
+}
+
+const DEFAULT_CHECKPOINT_INTERVAL: u32 = 10_000;
+
+impl Config {
+    pub fn new(network: ChainType) -> Self {
+        let stop = (network == ChainType::Mainnet).then_some(REFERENCE_HEIGHT);
+        Self {
+            data_dir: None,
+            cache: None,
+            network,
+            range: HeightRange::new(0, stop),
+            threads: 1,
+            checkpoint: None,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            resume: false,
+        }
+    }
+
+    /// Fail if threads or checkpoints are configured for `command`, which scans on one thread
+    /// without saving its progress.
+    pub fn require_sequential(&self, command: &str) -> Result<()> {
+        let unsupported: Vec<&str> = [
+            (self.threads > 1, "threads"),
+            (self.checkpoint.is_some(), "checkpoints"),
+            (self.resume, "resuming"),
+        ]
+        .into_iter()
+        .filter_map(|(set, what)| set.then_some(what))
+        .collect();
+        if unsupported.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Config(format!(
+            "`{command}` scans on a single thread without checkpoints, it does not support {}",
+            unsupported.join(", ")
+        )))
+    }
+
+    /// The directory holding `blocks` and `chainstate`. Like Bitcoin Core, networks other than
+    /// mainnet live in a subdirectory of the data directory unless it is given directly.
+    pub fn network_dir(&self) -> Option<PathBuf> {
+        let data_dir = self.data_dir.as_ref()?;
+        if self.network == ChainType::Mainnet || data_dir.join("blocks").exists() {
+            return Some(data_dir.clone());
+        }
+        Some(data_dir.join(network_name(self.network)))
+    }
+}
+
+/// Command line flags selecting the chain data and the height window to scan. A flag that is
+/// not given falls back to its environment variable.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ScanArgs {
+    /// Bitcoin Core data directory [env: BITCOIN_DIR]
+    #[arg(long, value_name = "DIR")]
+    pub datadir: Option<PathBuf>,
+    /// Read spent coins from a cache file instead of a data directory [env: CACHE]
+    #[arg(long, value_name = "FILE")]
+    pub cache: Option<PathBuf>,
+    /// mainnet, testnet3, testnet4, signet or regtest [env: NETWORK]
+    #[arg(long, value_parser = network_arg)]
+    pub network: Option<ChainType>,
+    /// First block height to scan [env: START_HEIGHT]
+    #[arg(long, value_name = "HEIGHT")]
+    pub start: Option<u32>,
+    /// Last block height to scan, defaults to 930000 on mainnet and the tip elsewhere
+    /// [env: STOP_HEIGHT]
+    #[arg(long, value_name = "HEIGHT")]
+    pub stop: Option<u32>,
+    /// Number of worker threads [env: THREADS]
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Periodically save the analysis state to this file [env: CHECKPOINT]
+    #[arg(long, value_name = "FILE")]
+    pub checkpoint: Option<PathBuf>,
+    /// Blocks between checkpoints [env: CHECKPOINT_INTERVAL]
+    #[arg(long, value_name = "BLOCKS")]
+    pub checkpoint_interval: Option<u32>,
+    /// Continue from the state saved in the checkpoint file [env: RESUME]
+    #[arg(long)]
+    pub resume: bool,
+}
+
+impl ScanArgs {
+    pub fn config(&self) -> Result<Config> {
+        self.config_from(|key| std::env::var(key).ok())
+    }
+
+    /// Resolve the flags into a configuration, reading unset values with `env`.
+    pub fn config_from(&self, env: impl Fn(&str) -> Option<String>) -> Result<Config> {
+        let network = match (self.network, env("NETWORK")) {
+            (Some(network), _) => network,
+            (None, Some(name)) => network_arg(&name).map_err(Error::Config)?,
+            (None, None) => ChainType::Mainnet,
+        };
+        let mut config = Config::new(network);
+        config.data_dir = self
+            .datadir
+            .clone()
+            .or_else(|| env("BITCOIN_DIR").map(PathBuf::from));
+        config.cache = self
+            .cache
+            .clone()
+            .or_else(|| env("CACHE").map(PathBuf::from));
+        if let Some(start) = flag_or_env(self.start, &env, "START_HEIGHT", "block height")? {
+            config.range.start = start;
+        }
+        if let Some(stop) = flag_or_env(self.stop, &env, "STOP_HEIGHT", "block height")? {
+            config.range.stop = Some(stop);
+        }
+        if let Some(threads) = flag_or_env(self.threads, &env, "THREADS", "thread count")? {
+            config.threads = threads;
+        }
+        config.checkpoint = self
+            .checkpoint
+            .clone()
+            .or_else(|| env("CHECKPOINT").map(PathBuf::from));
+        if let Some(interval) = flag_or_env(
+            self.checkpoint_interval,
+            &env,
+            "CHECKPOINT_INTERVAL",
+            "checkpoint interval",
+        )? {
+            config.checkpoint_interval = interval;
+        }
+        let resume = match env("RESUME") {
+            _ if self.resume => true,
+            Some(value) => parse_bool(&value, "resume setting")?,
+            None => false,
+        };
+        if resume && config.checkpoint.is_none() {
+            return Err(Error::Config(
+                "`--resume` requires a checkpoint file, set `CHECKPOINT` or pass `--checkpoint`"
+                    .into(),
+            ));
+        }
+        config.resume = resume;
+        Ok(config)
+    }
+}
+
+/// Parse a network name for a command line flag, naming the supported networks on failure.
+pub fn network_arg(name: &str) -> std::result::Result<ChainType, String> {
+    parse_network(name).ok_or_else(|| {
+        format!(
+            "unknown network `{name}`, expected one of mainnet, testnet3, testnet4, signet or regtest"
+        )
+    })
+}
+
+fn flag_or_env<T: std::str::FromStr>(
+    flag: Option<T>,
+    env: impl Fn(&str) -> Option<String>,
+    key: &str,
+    what: &str,
+) -> Result<Option<T>> {
+    match flag {
+        Some(value) => Ok(Some(value)),
+        None => env(key).map(|value| parse_number(&value, what)).transpose(),
+    }
+}
+
+/// Read a boolean environment variable, as `1`/`0` or `true`/`false`.
+fn parse_bool(value: &str, what: &str) -> Result<bool> {
+    match value {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(Error::Config(format!(
+            "`{value}` is not a valid {what}, expected 1, 0, true or false"
+        ))),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T> {
+    value
+        .parse::<T>()
+        .map_err(|_| Error::Config(format!("`{value}` is not a valid {what}")))
+}
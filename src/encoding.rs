@@ -0,0 +1,201 @@
+//! Bitcoin Core's integer serializations. They work with any [`Read`] or [`Write`], including
+//! byte slices, and never allocate.
+//!
+//! `VarInt` is the base-128 encoding used in the UTXO set and undo data, where each byte carries
+//! seven bits and a continuation flag, and one is subtracted from every byte but the last so that
+//! each value has exactly one encoding. `CompactSize` prefixes lengths and counts on the P2P wire
+//! with a marker byte for values that do not fit in one byte. Amounts in the UTXO set are
+//! compressed before being written as a `VarInt`, see [`compress_amount`].
+
+use std::io::{self, Read, Write};
+
+/// The largest vector size Core deserializes, enforced by range-checked `CompactSize` reads.
+pub const MAX_SIZE: u64 = 0x0200_0000;
+
+/// The longest `VarInt` encoding of a `u64`.
+pub const MAX_VARINT_SIZE: usize = 10;
+
+/// An `InvalidData` error, for malformed input to any decoder of the crate.
+pub(crate) fn invalid_data(msg: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn is_increasing(indices: &[u32]) -> bool {
+    indices.windows(2).all(|w| w[0] < w[1])
+}
+
+/// Panic unless `indices` are strictly increasing, as every encoding of index lists requires.
+pub(crate) fn check_increasing(indices: &[u32]) {
+    assert!(
+        is_increasing(indices),
+        "indices must be strictly increasing"
+    );
+}
+
+#[inline]
+pub const fn compact_size(value: u64) -> usize {
+    match value {
+        0..=0xFC => 1,
+        0xFD..=0xFFFF => 3,
+        0x10000..=0xFFFF_FFFF => 5,
+        _ => 9,
+    }
+}
+
+#[inline]
+pub const fn size_varint(mut n: u64) -> usize {
+    let mut size = 1;
+    while n > 0x7F {
+        n = (n >> 7) - 1;
+        size += 1;
+    }
+    size
+}
+
+pub fn write_varint<W: Write + ?Sized>(writer: &mut W, mut n: u64) -> io::Result<()> {
+    let mut buf = [0u8; MAX_VARINT_SIZE];
+    let mut pos = MAX_VARINT_SIZE;
+    loop {
+        pos -= 1;
+        let has_more = pos < MAX_VARINT_SIZE - 1;
+        buf[pos] = (n & 0x7F) as u8 | if has_more { 0x80 } else { 0x00 };
+        if n <= 0x7F {
+            break;
+        }
+        n = (n >> 7) - 1;
+    }
+    writer.write_all(&buf[pos..])
+}
+
+pub fn read_varint<R: Read + ?Sized>(reader: &mut R) -> io::Result<u64> {
+    let mut n: u64 = 0;
+    loop {
+        let byte = read_u8(reader)?;
+        if n > (u64::MAX >> 7) {
+            return Err(invalid_data("ReadVarInt(): size too large"));
+        }
+        n = (n << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+        n = n
+            .checked_add(1)
+            .ok_or_else(|| invalid_data("ReadVarInt(): size too large"))?;
+    }
+}
+
+pub fn write_compact_size<W: Write + ?Sized>(writer: &mut W, n: u64) -> io::Result<()> {
+    match n {
+        0..=0xFC => writer.write_all(&[n as u8]),
+        0xFD..=0xFFFF => {
+            writer.write_all(&[0xFD])?;
+            writer.write_all(&(n as u16).to_le_bytes())
+        }
+        0x10000..=0xFFFF_FFFF => {
+            writer.write_all(&[0xFE])?;
+            writer.write_all(&(n as u32).to_le_bytes())
+        }
+        _ => {
+            writer.write_all(&[0xFF])?;
+            writer.write_all(&n.to_le_bytes())
+        }
+    }
+}
+
+/// Read a `CompactSize`, rejecting values that are not in their shortest encoding. With
+/// `range_check`, values above [`MAX_SIZE`] are rejected as well, as Core does for sizes.
+pub fn read_compact_size<R: Read + ?Sized>(reader: &mut R, range_check: bool) -> io::Result<u64> {
+    let n = match read_u8(reader)? {
+        marker @ 0..=0xFC => marker as u64,
+        0xFD => {
+            let mut buf = [0u8; 2];
+            reader.read_exact(&mut buf)?;
+            let n = u16::from_le_bytes(buf) as u64;
+            if n < 0xFD {
+                return Err(invalid_data("non-canonical ReadCompactSize()"));
+            }
+            n
+        }
+        0xFE => {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            let n = u32::from_le_bytes(buf) as u64;
+            if n < 0x10000 {
+                return Err(invalid_data("non-canonical ReadCompactSize()"));
+            }
+            n
+        }
+        0xFF => {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            let n = u64::from_le_bytes(buf);
+            if n < 0x1_0000_0000 {
+                return Err(invalid_data("non-canonical ReadCompactSize()"));
+            }
+            n
+        }
+    };
+    if range_check && n > MAX_SIZE {
+        return Err(invalid_data("ReadCompactSize(): size too large"));
+    }
+    Ok(n)
+}
+
+/// Core's `CompressAmount`, which folds trailing decimal zeros into the low digit so that round
+/// amounts get short `VarInt` encodings.
+pub fn compress_amount(mut n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    let mut e: u64 = 0;
+    while n.is_multiple_of(10) && e < 9 {
+        n /= 10;
+        e += 1;
+    }
+    if e < 9 {
+        let d = n % 10;
+        assert!((1..=9).contains(&d));
+        n /= 10;
+        1 + (n * 9 + d - 1) * 10 + e
+    } else {
+        1 + (n - 1) * 10 + 9
+    }
+}
+
+/// Core's `DecompressAmount`, the inverse of [`compress_amount`]. Like Core, values that no amount
+/// compresses to wrap rather than fail.
+pub fn decompress_amount(mut x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+    x -= 1;
+    let mut e = x % 10;
+    x /= 10;
+    let mut n = if e < 9 {
+        let d = x % 9 + 1;
+        x /= 9;
+        x.wrapping_mul(10).wrapping_add(d)
+    } else {
+        x + 1
+    };
+    while e > 0 {
+        n = n.wrapping_mul(10);
+        e -= 1;
+    }
+    n
+}
+
+/// Write an amount as Core's `AmountCompression` does: compressed, then as a `VarInt`.
+pub fn write_amount<W: Write + ?Sized>(writer: &mut W, amount: u64) -> io::Result<()> {
+    write_varint(writer, compress_amount(amount))
+}
+
+pub fn read_amount<R: Read + ?Sized>(reader: &mut R) -> io::Result<u64> {
+    read_varint(reader).map(decompress_amount)
+}
+
+fn read_u8<R: Read + ?Sized>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
@@ -0,0 +1,161 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use kernel::KernelError;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Failures of the research tools, each mapped to a distinct process exit code.
+#[derive(Debug)]
+pub enum Error {
+    /// A setting from the environment or the command line is missing or malformed.
+    Config(String),
+    /// The kernel could not load or read the chainstate.
+    Kernel(KernelError),
+    /// A hints file could not be read.
+    Hintsfile {
+        path: PathBuf,
+        source: hintsfile::ReadError,
+    },
+    /// A hints file has no entry for a height at or below its stop height.
+    MissingHints { height: u32 },
+    /// A CSV table could not be read or written.
+    Csv { path: PathBuf, source: csv::Error },
+    /// A file could not be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// A checkpoint could not be saved or does not match the analysis being resumed.
+    Checkpoint {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// A plot could not be drawn.
+    Plot(String),
+    /// The coins spent by a block did not survive a serialization round trip.
+    Mismatch { height: u32, detail: String },
+    /// The unspent output indices of a block did not survive a round trip through an encoding.
+    RoundTrip {
+        encoding: &'static str,
+        height: u32,
+        detail: String,
+    },
+    /// A hints file does not match the outputs unspent at its stop height.
+    WrongHints {
+        path: PathBuf,
+        stop: u32,
+        mismatches: usize,
+    },
+}
+
+impl Error {
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        Self::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn csv(path: impl AsRef<Path>, source: csv::Error) -> Self {
+        Self::Csv {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            Self::Config(_) => 2,
+            Self::Io { .. } => 3,
+            Self::Kernel(_) => 4,
+            Self::Hintsfile { .. } | Self::MissingHints { .. } => 5,
+            Self::Csv { .. } => 6,
+            Self::Checkpoint { .. } => 7,
+            Self::Plot(_) => 8,
+            Self::Mismatch { .. } | Self::RoundTrip { .. } => 9,
+            Self::WrongHints { .. } => 10,
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(msg) => write!(f, "invalid configuration: {msg}"),
+            Self::Kernel(e) => write!(
+                f,
+                "kernel error: {e}. Check that the data directory holds a synced, unpruned node for the selected network"
+            ),
+            Self::Hintsfile { path, source } => {
+                write!(f, "could not read hints file {}: {source}", path.display())
+            }
+            Self::MissingHints { height } => {
+                write!(f, "hints file has no entry for height {height}")
+            }
+            Self::Csv { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Checkpoint { path, source } => write!(
+                f,
+                "checkpoint {}: {source}. Remove it to start the scan over",
+                path.display()
+            ),
+            Self::Plot(msg) => write!(f, "could not draw plot: {msg}"),
+            Self::Mismatch { height, detail } => {
+                write!(f, "inputs of block {height} do not round-trip: {detail}")
+            }
+            Self::RoundTrip {
+                encoding,
+                height,
+                detail,
+            } => write!(
+                f,
+                "{encoding} encoding of the unspent outputs of block {height} does not round-trip: {detail}"
+            ),
+            Self::WrongHints {
+                path,
+                stop,
+                mismatches,
+            } => write!(
+                f,
+                "hints file {} has {mismatches} hints that do not match the outputs unspent at height {stop}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Kernel(e) => Some(e),
+            Self::Hintsfile { source, .. } => Some(source),
+            Self::Csv { source, .. } => Some(source),
+            Self::Io { source, .. } => Some(source),
+            Self::Checkpoint { source, .. } => Some(source),
+            Self::Config(_)
+            | Self::MissingHints { .. }
+            | Self::Plot(_)
+            | Self::Mismatch { .. }
+            | Self::RoundTrip { .. }
+            | Self::WrongHints { .. } => None,
+        }
+    }
+}
+
+impl From<KernelError> for Error {
+    fn from(value: KernelError) -> Self {
+        Self::Kernel(value)
+    }
+}
+
+/// Print the error of a binary's `run` function and exit with its code.
+pub fn exit(result: Result<()>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            e.exit_code()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Golomb-Rice coding of strictly increasing indices, such as the unspent outputs of a block.
+//!
+//! The indices are sent as gaps: the first index, then the distance to each following index minus
+//! one. A gap is split by the Rice parameter `k` into a quotient, written in unary as ones ended by
+//! a zero, and `k` low bits. A list is serialized as its `CompactSize` length and, when it is not
+//! empty, a byte holding `k` followed by the gaps, most significant bit first, padded to a byte.
+
+use std::io;
+
+use crate::{
+    compact_size,
+    encoding::{check_increasing, invalid_data},
+    read_compact_size, write_compact_size,
+};
+
+/// The largest Rice parameter, enough for any gap between `u32` indices.
+pub const MAX_PARAMETER: u8 = 31;
+
+fn gaps(indices: &[u32]) -> impl Iterator<Item = u32> + '_ {
+    check_increasing(indices);
+    let mut prev = None;
+    indices.iter().map(move |&index| {
+        let gap = match prev {
+            None => index,
+            Some(prev) => index - prev - 1,
+        };
+        prev = Some(index);
+        gap
+    })
+}
+
+/// The number of bits of the gaps of `indices` with parameter `k`.
+fn coded_bits(indices: &[u32], k: u8) -> u64 {
+    gaps(indices)
+        .map(|gap| (gap >> k) as u64 + 1 + k as u64)
+        .sum()
+}
+
+/// The Rice parameter that codes the gaps of `indices` in the fewest bits.
+pub fn optimal_parameter(indices: &[u32]) -> u8 {
+    (0..=MAX_PARAMETER)
+        .min_by_key(|&k| coded_bits(indices, k))
+        .expect("the range is not empty")
+}
+
+/// The serialized size of `indices` with the optimal parameter.
+pub fn encoded_len(indices: &[u32]) -> usize {
+    let length = compact_size(indices.len() as u64);
+    if indices.is_empty() {
+        return length;
+    }
+    let bits = coded_bits(indices, optimal_parameter(indices));
+    length + 1 + bits.div_ceil(8) as usize
+}
+
+/// Serialize `indices`, which must be strictly increasing, with the optimal parameter.
+pub fn encode(indices: &[u32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(encoded_len(indices));
+    write_compact_size(&mut out, indices.len() as u64).expect("writing to a vector");
+    if indices.is_empty() {
+        return out;
+    }
+    let k = optimal_parameter(indices);
+    out.push(k);
+    let mut writer = BitWriter::new(out);
+    for gap in gaps(indices) {
+        for _ in 0..gap >> k {
+            writer.push(true);
+        }
+        writer.push(false);
+        for i in (0..k).rev() {
+            writer.push((gap >> i) & 1 == 1);
+        }
+    }
+    writer.finish()
+}
+
+/// Deserialize indices written by [`encode`], returning them and the number of bytes read.
+pub fn decode(bytes: &[u8]) -> io::Result<(Vec<u32>, usize)> {
+    let mut reader = bytes;
+    let n = read_compact_size(&mut reader, true)?;
+    let mut read = bytes.len() - reader.len();
+    if n == 0 {
+        return Ok((Vec::new(), read));
+    }
+    let (&k, rest) = reader.split_first().ok_or(io::ErrorKind::UnexpectedEof)?;
+    read += 1;
+    if k > MAX_PARAMETER {
+        return Err(invalid_data("Rice parameter is too large"));
+    }
+    // Every index takes at least its ending zero and `k` low bits, so the payload bounds the
+    // count worth allocating for.
+    let fit = rest.len() as u64 * 8 / (1 + k as u64);
+    let mut reader = BitReader::new(rest);
+    let mut indices = Vec::with_capacity(n.min(fit) as usize);
+    let mut next = 0u64;
+    for _ in 0..n {
+        let mut quotient = 0u64;
+        while reader.next()? {
+            quotient += 1;
+            if quotient > u32::MAX as u64 {
+                return Err(invalid_data("gap is too large"));
+            }
+        }
+        let mut remainder = 0u64;
+        for _ in 0..k {
+            remainder = (remainder << 1) | reader.next()? as u64;
+        }
+        let index = next + ((quotient << k) | remainder);
+        if index > u32::MAX as u64 {
+            return Err(invalid_data("index is too large"));
+        }
+        indices.push(index as u32);
+        next = index + 1;
+    }
+    Ok((indices, read + reader.bytes_read()))
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    byte: u8,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> Self {
+        Self {
+            out,
+            byte: 0,
+            bits: 0,
+        }
+    }
+
+    fn push(&mut self, bit: bool) {
+        self.byte = (self.byte << 1) | bit as u8;
+        self.bits += 1;
+        if self.bits == 8 {
+            self.out.push(self.byte);
+            self.byte = 0;
+            self.bits = 0;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.byte << (8 - self.bits));
+        }
+        self.out
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn next(&mut self) -> io::Result<bool> {
+        let byte = self
+            .bytes
+            .get(self.position / 8)
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1 == 1;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    fn bytes_read(&self) -> usize {
+        self.position.div_ceil(8)
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    io::{self, Write},
+};
+
+use hintsfile::{BuilderError, EliasFano, Hintsfile, HintsfileBuilder};
+
+use crate::{Error, Result};
+
+/// Decode the `encoding` of the unspent output `indices` of the block at `height` with
+/// `decode`, failing unless it reads back exactly the indices from all of `bytes`.
+pub fn check_round_trip(
+    encoding: &'static str,
+    height: u32,
+    indices: &[u32],
+    bytes: &[u8],
+    decode: impl FnOnce(&[u8]) -> io::Result<(Vec<u32>, usize)>,
+) -> Result<()> {
+    let round_trip = |detail| Error::RoundTrip {
+        encoding,
+        height,
+        detail,
+    };
+    let (decoded, read) = decode(bytes).map_err(|e| round_trip(e.to_string()))?;
+    if decoded != indices {
+        return Err(round_trip(format!(
+            "decoded {decoded:?}, expected {indices:?}"
+        )));
+    }
+    if read != bytes.len() {
+        return Err(round_trip(format!("read {read} of {} bytes", bytes.len())));
+    }
+    Ok(())
+}
+
+/// Scripts longer than this can never be spent, as in Core's `MAX_SCRIPT_SIZE`.
+const MAX_SCRIPT_SIZE: usize = 10_000;
+const OP_RETURN: u8 = 0x6a;
+
+/// Follows the outputs of blocks connected in height order to find those still unspent at the
+/// last connected block, the hints of a SwiftSync hints file.
+///
+/// An output is identified by its position among every output of its block, unspendable ones
+/// included. Unspendable outputs never enter the UTXO set, so they are never hinted.
+#[derive(Debug, Default)]
+pub struct HintsBuilder {
+    /// The height and index within its block of every unspent output, by outpoint.
+    unspent: HashMap<([u8; 32], u32), (u32, u32)>,
+    height: u32,
+    /// The number of outputs of each block, by height.
+    outputs: Vec<u32>,
+}
+
+impl HintsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The last height connected.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Connect the transaction `txid` of the block at `height`, spending the `inputs` outpoints
+    /// and creating outputs with the `scripts`. Transactions are connected in block order, from
+    /// height 1. The null outpoint of a coinbase input spends nothing.
+    pub fn connect_transaction<'a>(
+        &mut self,
+        height: u32,
+        txid: [u8; 32],
+        inputs: impl IntoIterator<Item = ([u8; 32], u32)>,
+        scripts: impl IntoIterator<Item = &'a [u8]>,
+    ) {
+        assert!(
+            height == self.height || height == self.height + 1,
+            "blocks must be connected in height order"
+        );
+        if height != self.height || self.outputs.is_empty() {
+            self.height = height;
+            self.outputs.resize(height as usize + 1, 0);
+        }
+        for outpoint in inputs {
+            self.unspent.remove(&outpoint);
+        }
+        for (vout, script) in scripts.into_iter().enumerate() {
+            let index = &mut self.outputs[height as usize];
+            if !is_unspendable(script) {
+                // A duplicate coinbase overwrites the unspent outputs of the first, as in Core.
+                self.unspent.insert((txid, vout as u32), (height, *index));
+            }
+            *index += 1;
+        }
+    }
+
+    /// The number of outputs of the block at `height`, unspendable ones included.
+    pub fn output_count(&self, height: u32) -> u32 {
+        self.outputs
+            .get(height as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The number of outputs unspent at the last connected height.
+    pub fn unspent_count(&self) -> usize {
+        self.unspent.len()
+    }
+
+    /// The sorted indices of the unspent outputs of each block that has any.
+    pub fn unspent_indices(&self) -> BTreeMap<u32, Vec<u32>> {
+        let mut indices: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for &(height, index) in self.unspent.values() {
+            indices.entry(height).or_default().push(index);
+        }
+        for block in indices.values_mut() {
+            block.sort_unstable();
+        }
+        indices
+    }
+
+    /// Write a hints file with the unspent outputs at every height up to the last connected one.
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        let indices = self.unspent_indices();
+        let mut builder = HintsfileBuilder::new(writer).initialize(self.height)?;
+        for height in 1..=self.height {
+            let block = indices.get(&height).map_or(&[][..], Vec::as_slice);
+            builder.append(EliasFano::compress(block))?;
+        }
+        builder.finish().map_err(|e| match e {
+            BuilderError::Io(e) => e,
+            e => io::Error::other(e.to_string()),
+        })
+    }
+
+    /// Compare the hints of `file` at every height up to its stop height with the outputs unspent
+    /// at the last connected height, which should be the stop height of the file. A height without
+    /// hints means the file is truncated or not a hints file for this chain, so it is an error
+    /// rather than a list of mismatches.
+    pub fn verify(&self, file: &Hintsfile) -> Result<Vec<HintMismatch>> {
+        let expected = self.unspent_indices();
+        let mut mismatches = Vec::new();
+        for height in 1..=file.stop_height() {
+            let hinted: BTreeSet<u32> = file
+                .indices_at_height(height)
+                .ok_or(Error::MissingHints { height })?
+                .into_iter()
+                .collect();
+            let unspent: BTreeSet<u32> = expected
+                .get(&height)
+                .map(|indices| indices.iter().copied().collect())
+                .unwrap_or_default();
+            let outputs = self.output_count(height);
+            for &index in unspent.difference(&hinted) {
+                mismatches.push(HintMismatch::Missing { height, index });
+            }
+            for &index in hinted.difference(&unspent) {
+                mismatches.push(if index < outputs {
+                    HintMismatch::Extra { height, index }
+                } else {
+                    HintMismatch::OutOfRange {
+                        height,
+                        index,
+                        outputs,
+                    }
+                });
+            }
+        }
+        mismatches.sort_by_key(|mismatch| (mismatch.height(), mismatch.index()));
+        Ok(mismatches)
+    }
+}
+
+/// A difference between a hints file and the outputs unspent at its stop height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintMismatch {
+    /// An unspent output that is not hinted.
+    Missing { height: u32, index: u32 },
+    /// A hinted output that is spent by the stop height, or can never be spent.
+    Extra { height: u32, index: u32 },
+    /// A hint past the last output of its block.
+    OutOfRange {
+        height: u32,
+        index: u32,
+        outputs: u32,
+    },
+}
+
+impl HintMismatch {
+    pub fn height(&self) -> u32 {
+        match *self {
+            Self::Missing { height, .. }
+            | Self::Extra { height, .. }
+            | Self::OutOfRange { height, .. } => height,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        match *self {
+            Self::Missing { index, .. }
+            | Self::Extra { index, .. }
+            | Self::OutOfRange { index, .. } => index,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Missing { .. } => "missing",
+            Self::Extra { .. } => "extra",
+            Self::OutOfRange { .. } => "out_of_range",
+        }
+    }
+}
+
+impl fmt::Display for HintMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Missing { height, index } => {
+                write!(
+                    f,
+                    "output {index} of block {height} is unspent but not hinted"
+                )
+            }
+            Self::Extra { height, index } => {
+                write!(
+                    f,
+                    "output {index} of block {height} is hinted but not unspent"
+                )
+            }
+            Self::OutOfRange {
+                height,
+                index,
+                outputs,
+            } => write!(
+                f,
+                "output {index} of block {height} is hinted but the block has {outputs} outputs"
+            ),
+        }
+    }
+}
+
+/// Whether an output can never be spent and so is left out of the UTXO set.
+fn is_unspendable(script: &[u8]) -> bool {
+    script.first() == Some(&OP_RETURN) || script.len() > MAX_SCRIPT_SIZE
+}
@@ -0,0 +1,192 @@
+//! A per-block choice between encodings of strictly increasing indices, such as the unspent
+//! outputs of a block.
+//!
+//! Each block is written as the tag of an [`Encoding`] followed by its payload, and the smallest
+//! encoding that applies is chosen. Blocks with every output spent are the tag alone, and blocks
+//! with every output unspent hold only the `CompactSize` number of outputs.
+
+use std::io;
+
+use hintsfile::EliasFano;
+
+use crate::{
+    MAX_SIZE, bitmap,
+    encoding::{check_increasing, invalid_data, is_increasing},
+    read_compact_size, read_varint, write_compact_size, write_varint,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Encoding {
+    /// No index.
+    AllSpent,
+    /// Every index from zero, as their number.
+    AllUnspent,
+    /// Elias-Fano, as in hints files.
+    EliasFano,
+    /// The `VarInt` count, then the first index and the difference to each following index.
+    RunLengths,
+    /// A plain bitmap, see [`bitmap::encode`].
+    Bitmap,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::AllSpent,
+        Encoding::AllUnspent,
+        Encoding::EliasFano,
+        Encoding::RunLengths,
+        Encoding::Bitmap,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::AllSpent => "all_spent",
+            Encoding::AllUnspent => "all_unspent",
+            Encoding::EliasFano => "elias_fano",
+            Encoding::RunLengths => "run_lengths",
+            Encoding::Bitmap => "bitmap",
+        }
+    }
+
+    fn tag(self) -> u8 {
+        self as u8
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        Self::ALL.get(tag as usize).copied()
+    }
+}
+
+/// Serialize `indices`, which must be strictly increasing, with `encoding`, or `None` if it cannot
+/// represent them.
+pub fn encode_with(encoding: Encoding, indices: &[u32]) -> Option<Vec<u8>> {
+    check_increasing(indices);
+    let every_index = indices
+        .last()
+        .is_some_and(|&last| last as usize + 1 == indices.len());
+    let mut out = vec![encoding.tag()];
+    match encoding {
+        Encoding::AllSpent if indices.is_empty() => {}
+        Encoding::AllUnspent if every_index => {
+            write_compact_size(&mut out, indices.len() as u64).expect("writing to a vector");
+        }
+        Encoding::AllSpent | Encoding::AllUnspent => return None,
+        Encoding::EliasFano if !elias_fano_fits(indices.len() as u64, indices.last()) => {
+            return None;
+        }
+        Encoding::EliasFano => EliasFano::compress(indices)
+            .write(&mut out)
+            .expect("writing to a vector"),
+        Encoding::RunLengths => {
+            write_varint(&mut out, indices.len() as u64).expect("writing to a vector");
+            let mut prev = 0;
+            for &index in indices {
+                write_varint(&mut out, (index - prev) as u64).expect("writing to a vector");
+                prev = index;
+            }
+        }
+        Encoding::Bitmap => out.extend(bitmap::encode(indices)),
+    }
+    Some(out)
+}
+
+/// Serialize `indices`, which must be strictly increasing, with the smallest encoding.
+pub fn encode(indices: &[u32]) -> (Encoding, Vec<u8>) {
+    Encoding::ALL
+        .into_iter()
+        .filter_map(|encoding| Some((encoding, encode_with(encoding, indices)?)))
+        .min_by_key(|(_, bytes)| bytes.len())
+        .expect("run-lengths encode any indices")
+}
+
+/// Deserialize indices written by [`encode`] or [`encode_with`], returning them and the number of
+/// bytes read.
+pub fn decode(bytes: &[u8]) -> io::Result<(Vec<u32>, usize)> {
+    let (&tag, mut reader) = bytes.split_first().ok_or(io::ErrorKind::UnexpectedEof)?;
+    let encoding = Encoding::from_tag(tag).ok_or_else(|| invalid_data("unknown encoding"))?;
+    let indices = match encoding {
+        Encoding::AllSpent => Vec::new(),
+        Encoding::AllUnspent => {
+            let n = read_compact_size(&mut reader, true)?;
+            if n == 0 {
+                return Err(invalid_data("no unspent outputs"));
+            }
+            (0..n as u32).collect()
+        }
+        Encoding::EliasFano => read_elias_fano(&mut reader)?,
+        Encoding::RunLengths => {
+            let n = read_count(&mut reader)?;
+            let mut indices = Vec::new();
+            let mut prev = 0u64;
+            for i in 0..n {
+                let diff = read_varint(&mut reader)?;
+                if i > 0 && diff == 0 {
+                    return Err(invalid_data("indices are not strictly increasing"));
+                }
+                prev = prev
+                    .checked_add(diff)
+                    .filter(|&index| index <= u32::MAX as u64)
+                    .ok_or_else(|| invalid_data("index is too large"))?;
+                indices.push(prev as u32);
+            }
+            indices
+        }
+        Encoding::Bitmap => {
+            let (indices, read) = bitmap::decode(reader)?;
+            reader = &reader[read..];
+            indices
+        }
+    };
+    Ok((indices, bytes.len() - reader.len()))
+}
+
+/// Read a `VarInt` count, rejecting those above [`MAX_SIZE`].
+fn read_count(reader: &mut &[u8]) -> io::Result<u64> {
+    let n = read_varint(reader)?;
+    if n > MAX_SIZE {
+        return Err(invalid_data("count is too large"));
+    }
+    Ok(n)
+}
+
+/// Whether `hintsfile` can encode `n` indices up to `last`: it works with the number past the
+/// largest index and packs fewer than 16 low bits of each.
+fn elias_fano_fits(n: u64, last: Option<&u32>) -> bool {
+    last.is_none_or(|&last| last < u32::MAX && (last as u64 + 1) / n < 1 << 16)
+}
+
+/// Read Elias-Fano indices, rejecting the input `hintsfile` would panic on, allocate more than
+/// the input for, or decode to indices that do not end at the largest index of the header.
+fn read_elias_fano(reader: &mut &[u8]) -> io::Result<Vec<u32>> {
+    let mut header = *reader;
+    let n = read_compact_size(&mut header, false)?;
+    if n == 0 {
+        return Ok(EliasFano::from_reader(reader)?.decompress());
+    }
+    let m = read_compact_size(&mut header, false)?;
+    if m > u32::MAX as u64 || n > m + 1 || !elias_fano_fits(n, Some(&(m as u32))) {
+        return Err(invalid_data("Elias-Fano header is out of range"));
+    }
+    let l = ((m + 1) / n).ilog2();
+    let low_len = (n * l as u64).div_ceil(8) as usize;
+    let high_len = (n + (m >> l)).div_ceil(8) as usize;
+    let high = header
+        .get(low_len..low_len + high_len)
+        .ok_or(io::ErrorKind::UnexpectedEof)?;
+    // Each index ends its unary upper bits with a set bit.
+    if high
+        .iter()
+        .map(|byte| byte.count_ones() as u64)
+        .sum::<u64>()
+        < n
+    {
+        return Err(invalid_data("Elias-Fano upper bits end early"));
+    }
+    let indices = EliasFano::from_reader(reader)?.decompress();
+    if !is_increasing(&indices) || indices.last() != Some(&(m as u32)) {
+        return Err(invalid_data(
+            "Elias-Fano indices do not increase to the largest",
+        ));
+    }
+    Ok(indices)
+}
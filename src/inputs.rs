@@ -0,0 +1,180 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    Error, Result, SpentBlock, SpentCoin, compact_size, compress_amount, encoding::invalid_data,
+    read_amount, read_compact_size, read_varint, script::ReconstructableScript, size_varint,
+    write_amount, write_compact_size, write_varint,
+};
+
+/// The coins spent by a block as SwiftSync sends them: the height of the block, then for each
+/// coin its age and coinbase flag, compressed amount and [`ReconstructableScript`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockInputs {
+    pub height: u32,
+    pub coins: Vec<InputCoin>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputCoin {
+    /// Blocks between the confirmation of the coin and the block spending it.
+    pub age: u32,
+    pub is_coinbase: bool,
+    pub amount: u64,
+    pub script: ReconstructableScript,
+}
+
+impl InputCoin {
+    fn code(&self) -> u64 {
+        ((self.age as u64) << 1) | self.is_coinbase as u64
+    }
+}
+
+impl BlockInputs {
+    pub fn from_block(block: &SpentBlock) -> io::Result<Self> {
+        let coins = block
+            .coins
+            .iter()
+            .map(|coin| {
+                let age = block
+                    .height
+                    .checked_sub(coin.confirmation_height)
+                    .ok_or_else(|| invalid_input("coin confirmed after the spending block"))?;
+                let amount =
+                    u64::try_from(coin.value).map_err(|_| invalid_input("negative amount"))?;
+                Ok(InputCoin {
+                    age,
+                    is_coinbase: coin.is_coinbase,
+                    amount,
+                    script: ReconstructableScript::from_script(&coin.script_pubkey),
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            height: block.height,
+            coins,
+        })
+    }
+
+    /// The spent coins, failing for coins older than the block or amounts that do not fit a
+    /// value, as [`decode`](Self::decode) does, and for scripts that cannot be rebuilt.
+    pub fn to_block(&self) -> io::Result<SpentBlock> {
+        let coins = self
+            .coins
+            .iter()
+            .map(|coin| {
+                let confirmation_height = self
+                    .height
+                    .checked_sub(coin.age)
+                    .ok_or_else(|| invalid_data("coin age exceeds the block height"))?;
+                let value =
+                    i64::try_from(coin.amount).map_err(|_| invalid_data("amount is too large"))?;
+                Ok(SpentCoin {
+                    confirmation_height,
+                    is_coinbase: coin.is_coinbase,
+                    value,
+                    script_pubkey: coin.script.to_script()?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(SpentBlock {
+            height: self.height,
+            coins,
+        })
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        let coins: usize = self
+            .coins
+            .iter()
+            .map(|coin| {
+                size_varint(coin.code())
+                    + size_varint(compress_amount(coin.amount))
+                    + coin.script.encoded_len()
+            })
+            .sum();
+        size_varint(self.height as u64) + compact_size(self.coins.len() as u64) + coins
+    }
+
+    pub fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        write_varint(writer, self.height as u64)?;
+        write_compact_size(writer, self.coins.len() as u64)?;
+        for coin in &self.coins {
+            write_varint(writer, coin.code())?;
+            write_amount(writer, coin.amount)?;
+            coin.script.encode(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let height =
+            u32::try_from(read_varint(reader)?).map_err(|_| invalid_data("height is too large"))?;
+        let count = read_compact_size(reader, true)?;
+        let coins = (0..count)
+            .map(|_| {
+                let code = read_varint(reader)?;
+                let age = u32::try_from(code >> 1)
+                    .ok()
+                    .filter(|&age| age <= height)
+                    .ok_or_else(|| invalid_data("coin age exceeds the block height"))?;
+                let amount = read_amount(reader)?;
+                if amount > i64::MAX as u64 {
+                    return Err(invalid_data("amount is too large"));
+                }
+                Ok(InputCoin {
+                    age,
+                    is_coinbase: code & 1 == 1,
+                    amount,
+                    script: ReconstructableScript::decode(reader)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { height, coins })
+    }
+}
+
+/// Serialize the coins spent by `block` and check that they deserialize back to the same coins,
+/// returning the serialized size.
+pub fn verify_block_inputs(block: &SpentBlock) -> Result<usize> {
+    let mismatch = |detail: String| Error::Mismatch {
+        height: block.height,
+        detail,
+    };
+    let inputs = BlockInputs::from_block(block).map_err(|e| mismatch(e.to_string()))?;
+    let mut buf = Vec::with_capacity(inputs.encoded_len());
+    inputs
+        .encode(&mut buf)
+        .map_err(|e| mismatch(e.to_string()))?;
+    if buf.len() != inputs.encoded_len() {
+        return Err(mismatch(format!(
+            "serialized {} bytes, expected {}",
+            buf.len(),
+            inputs.encoded_len()
+        )));
+    }
+    let mut reader = buf.as_slice();
+    let decoded = BlockInputs::decode(&mut reader).map_err(|e| mismatch(e.to_string()))?;
+    if !reader.is_empty() {
+        return Err(mismatch(format!("{} trailing bytes", reader.len())));
+    }
+    let decoded = decoded.to_block().map_err(|e| mismatch(e.to_string()))?;
+    if decoded.height != block.height || decoded.coins.len() != block.coins.len() {
+        return Err(mismatch(format!(
+            "decoded {} coins at height {}",
+            decoded.coins.len(),
+            decoded.height
+        )));
+    }
+    for (i, (decoded, coin)) in decoded.coins.iter().zip(&block.coins).enumerate() {
+        if decoded != coin {
+            return Err(mismatch(format!(
+                "coin {i} decoded as {decoded:?}, expected {coin:?}"
+            )));
+        }
+    }
+    Ok(buf.len())
+}
+
+fn invalid_input(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
@@ -0,0 +1,284 @@
+//! An adaptive binary range coder in the style of LZMA.
+//!
+//! Every bit is coded with a [`BitModel`] that learns the probability of a zero as it goes.
+//! Symbols from small alphabets are coded bit by bit down a [`BitTree`], and integers by their
+//! bit length followed by their leading bits under an [`IntegerModel`].
+
+use std::io;
+
+const PROB_BITS: u32 = 11;
+const PROB_ONE: u16 = 1 << PROB_BITS;
+const MOVE_BITS: u32 = 5;
+const TOP: u32 = 1 << 24;
+
+/// The adaptive probability that the next bit is zero, out of `2^11`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitModel(u16);
+
+impl Default for BitModel {
+    fn default() -> Self {
+        Self(PROB_ONE / 2)
+    }
+}
+
+impl BitModel {
+    fn bound(self, range: u32) -> u32 {
+        (range >> PROB_BITS) * self.0 as u32
+    }
+
+    fn update(&mut self, bit: bool) {
+        if bit {
+            self.0 -= self.0 >> MOVE_BITS;
+        } else {
+            self.0 += (PROB_ONE - self.0) >> MOVE_BITS;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RangeEncoder {
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    out: Vec<u8>,
+}
+
+impl Default for RangeEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RangeEncoder {
+    pub fn new() -> Self {
+        Self {
+            low: 0,
+            range: u32::MAX,
+            cache: 0,
+            cache_size: 1,
+            out: Vec::new(),
+        }
+    }
+
+    pub fn encode_bit(&mut self, model: &mut BitModel, bit: bool) {
+        let bound = model.bound(self.range);
+        if bit {
+            self.low += bound as u64;
+            self.range -= bound;
+        } else {
+            self.range = bound;
+        }
+        model.update(bit);
+        self.normalize();
+    }
+
+    /// Code the low `bits` of `value`, most significant first, each with probability one half.
+    pub fn encode_direct(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            self.range >>= 1;
+            if (value >> i) & 1 == 1 {
+                self.low += self.range as u64;
+            }
+            self.normalize();
+        }
+    }
+
+    /// Flush the coder, returning the coded bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        for _ in 0..5 {
+            self.shift_low();
+        }
+        // The first byte is always zero as the interval never grows past its initial bound.
+        self.out.remove(0);
+        self.out
+    }
+
+    fn normalize(&mut self) {
+        while self.range < TOP {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+
+    fn shift_low(&mut self) {
+        if (self.low as u32) < 0xFF00_0000 || self.low >> 32 != 0 {
+            let carry = (self.low >> 32) as u8;
+            let mut byte = self.cache;
+            loop {
+                self.out.push(byte.wrapping_add(carry));
+                byte = 0xFF;
+                self.cache_size -= 1;
+                if self.cache_size == 0 {
+                    break;
+                }
+            }
+            self.cache = (self.low >> 24) as u8;
+        }
+        self.cache_size += 1;
+        self.low = ((self.low as u32) << 8) as u64;
+    }
+}
+
+#[derive(Debug)]
+pub struct RangeDecoder<'a> {
+    input: &'a [u8],
+    range: u32,
+    code: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    pub fn new(input: &'a [u8]) -> io::Result<Self> {
+        let mut decoder = Self {
+            input,
+            range: u32::MAX,
+            code: 0,
+        };
+        for _ in 0..4 {
+            decoder.code = (decoder.code << 8) | decoder.next_byte()? as u32;
+        }
+        Ok(decoder)
+    }
+
+    pub fn decode_bit(&mut self, model: &mut BitModel) -> io::Result<bool> {
+        let bound = model.bound(self.range);
+        let bit = self.code >= bound;
+        if bit {
+            self.code -= bound;
+            self.range -= bound;
+        } else {
+            self.range = bound;
+        }
+        model.update(bit);
+        self.normalize()?;
+        Ok(bit)
+    }
+
+    pub fn decode_direct(&mut self, bits: u32) -> io::Result<u64> {
+        let mut value = 0;
+        for _ in 0..bits {
+            self.range >>= 1;
+            let bit = self.code >= self.range;
+            if bit {
+                self.code -= self.range;
+            }
+            value = (value << 1) | bit as u64;
+            self.normalize()?;
+        }
+        Ok(value)
+    }
+
+    fn normalize(&mut self) -> io::Result<()> {
+        while self.range < TOP {
+            self.range <<= 8;
+            self.code = (self.code << 8) | self.next_byte()? as u32;
+        }
+        Ok(())
+    }
+
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let (&byte, rest) = self
+            .input
+            .split_first()
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        self.input = rest;
+        Ok(byte)
+    }
+}
+
+/// Adaptive models for symbols of `bits` bits, one per node of a binary tree.
+#[derive(Debug, Clone)]
+pub struct BitTree {
+    bits: u32,
+    models: Vec<BitModel>,
+}
+
+impl BitTree {
+    pub fn new(bits: u32) -> Self {
+        Self {
+            bits,
+            models: vec![BitModel::default(); 1 << bits],
+        }
+    }
+
+    pub fn encode(&mut self, encoder: &mut RangeEncoder, symbol: u32) {
+        let mut node = 1;
+        for i in (0..self.bits).rev() {
+            let bit = (symbol >> i) & 1 == 1;
+            encoder.encode_bit(&mut self.models[node], bit);
+            node = (node << 1) | bit as usize;
+        }
+    }
+
+    pub fn decode(&mut self, decoder: &mut RangeDecoder) -> io::Result<u32> {
+        let mut node = 1;
+        for _ in 0..self.bits {
+            let bit = decoder.decode_bit(&mut self.models[node])?;
+            node = (node << 1) | bit as usize;
+        }
+        Ok((node - (1 << self.bits)) as u32)
+    }
+}
+
+/// Bits below the leading one of an integer that are coded adaptively, the rest are coded direct.
+const MODELED_BITS: u32 = 4;
+
+/// Adaptive model of integers: the bit length, then the bits after the leading one, the first
+/// [`MODELED_BITS`] of them with a separate tree for each length.
+#[derive(Debug, Clone)]
+pub struct IntegerModel {
+    lengths: BitTree,
+    mantissas: Vec<BitTree>,
+}
+
+impl Default for IntegerModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IntegerModel {
+    pub fn new() -> Self {
+        Self {
+            lengths: BitTree::new(7),
+            mantissas: (0..=u64::BITS)
+                .map(|len| BitTree::new(len.saturating_sub(1).min(MODELED_BITS)))
+                .collect(),
+        }
+    }
+
+    pub fn encode(&mut self, encoder: &mut RangeEncoder, value: u64) {
+        let len = u64::BITS - value.leading_zeros();
+        self.lengths.encode(encoder, len);
+        if len <= 1 {
+            return;
+        }
+        let (modeled, direct) = split(len);
+        let leading = (value >> direct) & ((1 << modeled) - 1);
+        self.mantissas[len as usize].encode(encoder, leading as u32);
+        encoder.encode_direct(value, direct);
+    }
+
+    pub fn decode(&mut self, decoder: &mut RangeDecoder) -> io::Result<u64> {
+        let len = self.lengths.decode(decoder)?;
+        if len > u64::BITS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "integer is too long",
+            ));
+        }
+        if len <= 1 {
+            return Ok(len as u64);
+        }
+        let (modeled, direct) = split(len);
+        let leading = self.mantissas[len as usize].decode(decoder)? as u64;
+        let low = decoder.decode_direct(direct)?;
+        Ok((((1 << modeled) | leading) << direct) | low)
+    }
+}
+
+/// The number of modeled and direct bits below the leading one of a `len` bit integer.
+fn split(len: u32) -> (u32, u32) {
+    let modeled = (len - 1).min(MODELED_BITS);
+    (modeled, len - 1 - modeled)
+}
@@ -0,0 +1,33 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// The results of an analysis along with what was scanned, for writing as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report<T = Value> {
+    /// The version of this crate that produced the report.
+    pub version: String,
+    pub network: String,
+    /// The inclusive window of block heights scanned.
+    pub start_height: u32,
+    pub stop_height: u32,
+    pub results: T,
+}
+
+impl<T: Serialize> Report<T> {
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).map_err(|e| Error::io(path, e))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, self).map_err(|e| Error::io(path, e.into()))?;
+        writer.flush().map_err(|e| Error::io(path, e))?;
+        println!("Wrote report to {}", path.display());
+        Ok(())
+    }
+}
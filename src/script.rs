@@ -0,0 +1,566 @@
+//! Classifying scriptPubKeys by template, and the `ReconstructableScript` format, which stores a
+//! scriptPubKey as a template tag and the data the template cannot imply.
+//!
+//! Tags 0 through 5 follow Core's `ScriptCompression`, as does the compression of keys. Scripts
+//! matching no template, and keys that are hybrid or not points on the curve, are stored whole
+//! behind [`OTHER`], so every script decodes back byte-for-byte.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    str::FromStr,
+};
+
+use secp256k1::PublicKey;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{compact_size, read_compact_size, write_compact_size};
+
+pub const P2PKH: u8 = 0;
+pub const P2SH: u8 = 1;
+/// P2PK with a compressed key, the tag being the key's own `0x02` or `0x03` prefix.
+pub const P2PK_EVEN: u8 = 2;
+pub const P2PK_ODD: u8 = 3;
+/// P2PK with an uncompressed key, stored as its x-coordinate and recovered by decompressing the
+/// point. The tag is 4 plus the parity of y.
+pub const P2PK_UNCOMPRESSED_EVEN: u8 = 4;
+pub const P2PK_UNCOMPRESSED_ODD: u8 = 5;
+pub const P2WPKH: u8 = 6;
+pub const P2WSH: u8 = 7;
+pub const P2TR: u8 = 8;
+pub const OTHER: u8 = 9;
+/// Bare multisig, stored as `m` and `n` packed in a byte followed by each compressed key.
+pub const MULTISIG: u8 = 10;
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4C;
+const OP_PUSHDATA2: u8 = 0x4D;
+const OP_PUSHDATA4: u8 = 0x4E;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_RETURN: u8 = 0x6A;
+const OP_DUP: u8 = 0x76;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_HASH160: u8 = 0xA9;
+const OP_CHECKSIG: u8 = 0xAC;
+const OP_CHECKMULTISIG: u8 = 0xAE;
+
+/// The most keys of a multisig Core's `Solver` recognizes.
+const MAX_PUBKEYS_PER_MULTISIG: u8 = 20;
+
+/// The template of a scriptPubKey, matched as Core's `Solver` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ScriptType {
+    P2pk,
+    P2pkUncompressed,
+    /// P2PK with a hybrid key, an uncompressed key whose prefix also encodes the y parity.
+    P2pkHybrid,
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    /// Pay-to-anchor, the keyless witness v1 output `OP_1 <0x4e73>`.
+    P2a,
+    /// Bare `m`-of-`n` multisig.
+    Multisig {
+        required: u8,
+        keys: u8,
+    },
+    /// `OP_RETURN` followed only by pushes.
+    NullData,
+    /// A witness program of an undefined version or length.
+    WitnessUnknown {
+        version: u8,
+    },
+    NonStandard,
+}
+
+impl fmt::Display for ScriptType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::P2pk => f.write_str("p2pk"),
+            Self::P2pkUncompressed => f.write_str("p2pk_uncompressed"),
+            Self::P2pkHybrid => f.write_str("p2pk_hybrid"),
+            Self::P2pkh => f.write_str("p2pkh"),
+            Self::P2sh => f.write_str("p2sh"),
+            Self::P2wpkh => f.write_str("p2wpkh"),
+            Self::P2wsh => f.write_str("p2wsh"),
+            Self::P2tr => f.write_str("p2tr"),
+            Self::P2a => f.write_str("p2a"),
+            Self::Multisig { required, keys } => write!(f, "multisig_{required}_of_{keys}"),
+            Self::NullData => f.write_str("op_return"),
+            Self::WitnessUnknown { version } => write!(f, "witness_v{version}_unknown"),
+            Self::NonStandard => f.write_str("nonstandard"),
+        }
+    }
+}
+
+impl FromStr for ScriptType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "p2pk" => Self::P2pk,
+            "p2pk_uncompressed" => Self::P2pkUncompressed,
+            "p2pk_hybrid" => Self::P2pkHybrid,
+            "p2pkh" => Self::P2pkh,
+            "p2sh" => Self::P2sh,
+            "p2wpkh" => Self::P2wpkh,
+            "p2wsh" => Self::P2wsh,
+            "p2tr" => Self::P2tr,
+            "p2a" => Self::P2a,
+            "op_return" => Self::NullData,
+            "nonstandard" => Self::NonStandard,
+            _ => {
+                let parsed = if let Some(m_of_n) = s.strip_prefix("multisig_") {
+                    m_of_n.split_once("_of_").and_then(|(required, keys)| {
+                        Some(Self::Multisig {
+                            required: required.parse().ok()?,
+                            keys: keys.parse().ok()?,
+                        })
+                    })
+                } else {
+                    s.strip_prefix("witness_v")
+                        .and_then(|s| s.strip_suffix("_unknown"))
+                        .and_then(|version| version.parse().ok())
+                        .map(|version| Self::WitnessUnknown { version })
+                };
+                return parsed.ok_or_else(|| format!("unknown script type `{s}`"));
+            }
+        })
+    }
+}
+
+impl Serialize for ScriptType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScriptType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+pub fn classify(script: &[u8]) -> ScriptType {
+    match script {
+        [33, 0x02 | 0x03, key @ .., OP_CHECKSIG] if key.len() == 32 => ScriptType::P2pk,
+        [65, 0x04, point @ .., OP_CHECKSIG] if point.len() == 64 => ScriptType::P2pkUncompressed,
+        [65, 0x06 | 0x07, point @ .., OP_CHECKSIG] if point.len() == 64 => ScriptType::P2pkHybrid,
+        [
+            OP_DUP,
+            OP_HASH160,
+            20,
+            hash @ ..,
+            OP_EQUALVERIFY,
+            OP_CHECKSIG,
+        ] if hash.len() == 20 => ScriptType::P2pkh,
+        [OP_HASH160, 20, hash @ .., OP_EQUAL] if hash.len() == 20 => ScriptType::P2sh,
+        [OP_RETURN, rest @ ..] if push_only(rest) => ScriptType::NullData,
+        _ => {
+            if let Some((version, program)) = witness_program(script) {
+                match (version, program.len()) {
+                    (0, 20) => ScriptType::P2wpkh,
+                    (0, 32) => ScriptType::P2wsh,
+                    (0, _) => ScriptType::NonStandard,
+                    (1, 32) => ScriptType::P2tr,
+                    (1, 2) if program == [0x4E, 0x73] => ScriptType::P2a,
+                    (version, _) => ScriptType::WitnessUnknown { version },
+                }
+            } else if let Some((required, keys)) = multisig(script) {
+                ScriptType::Multisig {
+                    required,
+                    keys: keys.len() as u8,
+                }
+            } else {
+                ScriptType::NonStandard
+            }
+        }
+    }
+}
+
+/// The version and program of a witness output, a version opcode followed by a single push of
+/// 2 to 40 bytes.
+fn witness_program(script: &[u8]) -> Option<(u8, &[u8])> {
+    let [version, len, program @ ..] = script else {
+        return None;
+    };
+    let version = match *version {
+        OP_0 => 0,
+        OP_1..=OP_16 => version - OP_1 + 1,
+        _ => return None,
+    };
+    ((2..=40).contains(len) && program.len() == *len as usize).then_some((version, program))
+}
+
+/// The `m` and keys of `<m> <pubkey>... <n> OP_CHECKMULTISIG`, as Core's `MatchMultisig`. Keys
+/// may be pushed with any push opcode but must be the size their prefix implies, and `m` and `n`
+/// are `OP_1` to `OP_16` or minimal pushes of up to [`MAX_PUBKEYS_PER_MULTISIG`].
+fn multisig(script: &[u8]) -> Option<(u8, Vec<&[u8]>)> {
+    let [ops @ .., OP_CHECKMULTISIG] = script else {
+        return None;
+    };
+    let mut ops = ops;
+    let (opcode, data) = next_op(&mut ops)?;
+    let required = script_number(opcode, data, 1)?;
+    let mut keys = Vec::new();
+    let (opcode, data) = loop {
+        let (opcode, data) = next_op(&mut ops)?;
+        if !is_key_size(data) {
+            break (opcode, data);
+        }
+        keys.push(data);
+    };
+    let count = script_number(opcode, data, required)?;
+    (ops.is_empty() && keys.len() == count as usize).then_some((required, keys))
+}
+
+/// A number from `min` to [`MAX_PUBKEYS_PER_MULTISIG`], as Core's `GetScriptNumber`. Those above
+/// 16 can only be pushed minimally as a single byte.
+fn script_number(opcode: u8, data: &[u8], min: u8) -> Option<u8> {
+    let n = match (opcode, data) {
+        (OP_1..=OP_16, _) => opcode - OP_1 + 1,
+        (1, &[n]) if n > 16 => n,
+        _ => return None,
+    };
+    (min..=MAX_PUBKEYS_PER_MULTISIG).contains(&n).then_some(n)
+}
+
+/// Whether `data` is the size of a public key with its prefix, as Core's `CPubKey::ValidSize`.
+fn is_key_size(data: &[u8]) -> bool {
+    match data.first() {
+        Some(0x02 | 0x03) => data.len() == 33,
+        Some(0x04 | 0x06 | 0x07) => data.len() == 65,
+        _ => false,
+    }
+}
+
+/// Split the next opcode and the data it pushes off `script`, as Core's `GetScriptOp`.
+fn next_op<'a>(script: &mut &'a [u8]) -> Option<(u8, &'a [u8])> {
+    let (&opcode, rest) = script.split_first()?;
+    let (len, rest) = match opcode {
+        len @ 0x01..OP_PUSHDATA1 => (len as usize, rest),
+        OP_PUSHDATA1 => {
+            let (&len, rest) = rest.split_first()?;
+            (len as usize, rest)
+        }
+        OP_PUSHDATA2 => {
+            let (len, rest) = rest.split_first_chunk()?;
+            (u16::from_le_bytes(*len) as usize, rest)
+        }
+        OP_PUSHDATA4 => {
+            let (len, rest) = rest.split_first_chunk()?;
+            (u32::from_le_bytes(*len) as usize, rest)
+        }
+        _ => (0, rest),
+    };
+    let data = rest.get(..len)?;
+    *script = &rest[len..];
+    Some((opcode, data))
+}
+
+/// Whether a script consists only of push opcodes, `OP_RESERVED` included as in Core.
+fn push_only(mut script: &[u8]) -> bool {
+    while !script.is_empty() {
+        match next_op(&mut script) {
+            Some((opcode, _)) if opcode <= OP_16 => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReconstructableScript {
+    P2pkh([u8; 20]),
+    P2sh([u8; 20]),
+    /// A compressed key. Its `0x02` or `0x03` prefix doubles as the tag of the encoding.
+    P2pk([u8; 33]),
+    /// The x and y coordinates of the key, without its `0x04` prefix. Only points on the curve
+    /// may be encoded, as y is dropped.
+    P2pkUncompressed([u8; 64]),
+    P2wpkh([u8; 20]),
+    P2wsh([u8; 32]),
+    P2tr([u8; 32]),
+    /// Bare `m`-of-`n` multisig. Every key is compressed, or uncompressed and a point on the curve.
+    Multisig {
+        required: u8,
+        keys: Vec<Vec<u8>>,
+    },
+    Other(Vec<u8>),
+}
+
+impl ReconstructableScript {
+    pub fn from_script(script: &[u8]) -> Self {
+        match classify(script) {
+            ScriptType::P2pkh => Self::P2pkh(script[3..23].try_into().unwrap()),
+            ScriptType::P2sh => Self::P2sh(script[2..22].try_into().unwrap()),
+            ScriptType::P2pk => Self::P2pk(script[1..34].try_into().unwrap()),
+            ScriptType::P2pkUncompressed if compress_key(&script[1..66]).is_some() => {
+                Self::P2pkUncompressed(script[2..66].try_into().unwrap())
+            }
+            ScriptType::P2wpkh => Self::P2wpkh(script[2..].try_into().unwrap()),
+            ScriptType::P2wsh => Self::P2wsh(script[2..].try_into().unwrap()),
+            ScriptType::P2tr => Self::P2tr(script[2..].try_into().unwrap()),
+            ScriptType::Multisig { .. } => {
+                let (required, keys) = multisig(script).expect("classified as multisig");
+                if !keys.iter().all(|key| compress_key(key).is_some()) {
+                    return Self::Other(script.to_vec());
+                }
+                let keys = keys.into_iter().map(<[u8]>::to_vec).collect();
+                let multisig = Self::Multisig { required, keys };
+                // Only the script rebuilt from the keys, with direct pushes and up to 16 keys,
+                // is sent as a multisig.
+                if multisig.to_script().is_ok_and(|rebuilt| rebuilt == script) {
+                    multisig
+                } else {
+                    Self::Other(script.to_vec())
+                }
+            }
+            _ => Self::Other(script.to_vec()),
+        }
+    }
+
+    /// The scriptPubKey, failing for a multisig that is not 1 to 16 keys of which 1 to all are
+    /// required.
+    pub fn to_script(&self) -> io::Result<Vec<u8>> {
+        Ok(match self {
+            Self::P2pkh(hash) => [
+                &[OP_DUP, OP_HASH160, 20][..],
+                hash,
+                &[OP_EQUALVERIFY, OP_CHECKSIG],
+            ]
+            .concat(),
+            Self::P2sh(hash) => [&[OP_HASH160, 20][..], hash, &[OP_EQUAL]].concat(),
+            Self::P2pk(key) => {
+                check_compressed(key)?;
+                [&[33][..], key, &[OP_CHECKSIG]].concat()
+            }
+            Self::P2pkUncompressed(point) => [&[65, 0x04][..], point, &[OP_CHECKSIG]].concat(),
+            Self::P2wpkh(hash) => [&[OP_0, 20][..], hash].concat(),
+            Self::P2wsh(hash) => [&[OP_0, 32][..], hash].concat(),
+            Self::P2tr(key) => [&[OP_1, 32][..], key].concat(),
+            Self::Multisig { required, keys } => {
+                let (m, n) = multisig_opcodes(*required, keys.len()).ok_or_else(|| {
+                    invalid_multisig(io::ErrorKind::InvalidInput, *required, keys.len())
+                })?;
+                let mut script = vec![m];
+                for key in keys {
+                    let len = u8::try_from(key.len())
+                        .ok()
+                        .filter(|&len| len < OP_PUSHDATA1)
+                        .ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidInput, "multisig key is too long")
+                        })?;
+                    script.push(len);
+                    script.extend_from_slice(key);
+                }
+                script.extend([n, OP_CHECKMULTISIG]);
+                script
+            }
+            Self::Other(script) => script.clone(),
+        })
+    }
+
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::P2pkh(_) => P2PKH,
+            Self::P2sh(_) => P2SH,
+            Self::P2pk(key) => key[0],
+            Self::P2pkUncompressed(point) => P2PK_UNCOMPRESSED_EVEN + (point[63] & 1),
+            Self::P2wpkh(_) => P2WPKH,
+            Self::P2wsh(_) => P2WSH,
+            Self::P2tr(_) => P2TR,
+            Self::Multisig { .. } => MULTISIG,
+            Self::Other(_) => OTHER,
+        }
+    }
+
+    fn payload(&self) -> &[u8] {
+        match self {
+            Self::P2pkh(hash) | Self::P2sh(hash) | Self::P2wpkh(hash) => hash,
+            Self::P2pk(key) => &key[1..],
+            Self::P2pkUncompressed(point) => &point[..32],
+            Self::P2wsh(hash) | Self::P2tr(hash) => hash,
+            Self::Multisig { .. } => &[],
+            Self::Other(script) => script,
+        }
+    }
+
+    /// Size of the encoding, the tag included.
+    pub fn encoded_len(&self) -> usize {
+        let payload = self.payload().len();
+        match self {
+            Self::Multisig { keys, .. } => 2 + 33 * keys.len(),
+            Self::Other(_) => 1 + compact_size(payload as u64) + payload,
+            _ => 1 + payload,
+        }
+    }
+
+    pub fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let not_on_curve = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "uncompressed key is not a point on the curve",
+            )
+        };
+        match self {
+            Self::P2pk(key) => {
+                check_compressed(key)?;
+                writer.write_all(key)
+            }
+            Self::P2pkUncompressed(point) => {
+                let key = compress_key(&[&[0x04][..], point].concat()).ok_or_else(not_on_curve)?;
+                writer.write_all(&key)
+            }
+            Self::Multisig { required, keys } => {
+                if multisig_opcodes(*required, keys.len()).is_none() {
+                    return Err(invalid_multisig(
+                        io::ErrorKind::InvalidInput,
+                        *required,
+                        keys.len(),
+                    ));
+                }
+                let keys = keys
+                    .iter()
+                    .map(|key| compress_key(key).ok_or_else(not_on_curve))
+                    .collect::<io::Result<Vec<_>>>()?;
+                writer.write_all(&[MULTISIG, (required - 1) << 4 | (keys.len() as u8 - 1)])?;
+                keys.iter().try_for_each(|key| writer.write_all(key))
+            }
+            Self::Other(script) => {
+                writer.write_all(&[OTHER])?;
+                write_compact_size(writer, script.len() as u64)?;
+                writer.write_all(script)
+            }
+            _ => {
+                writer.write_all(&[self.tag()])?;
+                writer.write_all(self.payload())
+            }
+        }
+    }
+
+    pub fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let mut tag = [0u8; 1];
+        reader.read_exact(&mut tag)?;
+        Ok(match tag[0] {
+            P2PKH => Self::P2pkh(read_array(reader)?),
+            P2SH => Self::P2sh(read_array(reader)?),
+            P2PK_EVEN | P2PK_ODD => {
+                let mut key = [tag[0]; 33];
+                reader.read_exact(&mut key[1..])?;
+                Self::P2pk(key)
+            }
+            P2PK_UNCOMPRESSED_EVEN | P2PK_UNCOMPRESSED_ODD => {
+                let mut key = [tag[0]; 33];
+                reader.read_exact(&mut key[1..])?;
+                Self::P2pkUncompressed(decompress_key(&key)?[1..].try_into().unwrap())
+            }
+            P2WPKH => Self::P2wpkh(read_array(reader)?),
+            P2WSH => Self::P2wsh(read_array(reader)?),
+            P2TR => Self::P2tr(read_array(reader)?),
+            MULTISIG => {
+                let [packed] = read_array(reader)?;
+                let (required, count) = ((packed >> 4) + 1, (packed & 0x0F) + 1);
+                if multisig_opcodes(required, count as usize).is_none() {
+                    return Err(invalid_multisig(
+                        io::ErrorKind::InvalidData,
+                        required,
+                        count as usize,
+                    ));
+                }
+                let keys = (0..count)
+                    .map(|_| decompress_key(&read_array(reader)?))
+                    .collect::<io::Result<_>>()?;
+                Self::Multisig { required, keys }
+            }
+            OTHER => {
+                let len = read_compact_size(reader, true)?;
+                let mut script = Vec::new();
+                Read::take(&mut *reader, len).read_to_end(&mut script)?;
+                if script.len() as u64 != len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                Self::Other(script)
+            }
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown script tag {tag}"),
+                ));
+            }
+        })
+    }
+}
+
+/// The opcodes pushing the number of `required` signatures and of `keys` of a bare multisig, or
+/// `None` unless it has 1 to 16 keys of which 1 to all are required.
+fn multisig_opcodes(required: u8, keys: usize) -> Option<(u8, u8)> {
+    let keys = u8::try_from(keys)
+        .ok()
+        .filter(|keys| (1..=16).contains(keys))?;
+    if !(1..=keys).contains(&required) {
+        return None;
+    }
+    Some((OP_1.checked_add(required - 1)?, OP_1.checked_add(keys - 1)?))
+}
+
+/// Reject a P2PK key that is not compressed, as its prefix would be written as another tag.
+fn check_compressed(key: &[u8; 33]) -> io::Result<()> {
+    match key[0] {
+        P2PK_EVEN | P2PK_ODD => Ok(()),
+        prefix => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("P2PK key prefix {prefix:#04x} is not compressed"),
+        )),
+    }
+}
+
+fn invalid_multisig(kind: io::ErrorKind, required: u8, keys: usize) -> io::Error {
+    io::Error::new(
+        kind,
+        format!("{required}-of-{keys} is not a valid multisig"),
+    )
+}
+
+/// Core's key compression: compressed keys as they are, and uncompressed keys that are points on
+/// the curve as their x-coordinate behind 4 plus the parity of y.
+fn compress_key(key: &[u8]) -> Option<[u8; 33]> {
+    match key {
+        [0x02 | 0x03, ..] if key.len() == 33 => key.try_into().ok(),
+        [0x04, point @ ..] if point.len() == 64 && PublicKey::from_slice(key).is_ok() => {
+            let mut compressed = [0x04 + (point[63] & 1); 33];
+            compressed[1..].copy_from_slice(&point[..32]);
+            Some(compressed)
+        }
+        _ => None,
+    }
+}
+
+fn decompress_key(compressed: &[u8; 33]) -> io::Result<Vec<u8>> {
+    match compressed[0] {
+        0x02 | 0x03 => Ok(compressed.to_vec()),
+        0x04 | 0x05 => {
+            let mut key = *compressed;
+            key[0] -= 2;
+            let key = PublicKey::from_slice(&key)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "x is not on the curve"))?;
+            Ok(key.serialize_uncompressed().to_vec())
+        }
+        prefix => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown key prefix {prefix}"),
+        )),
+    }
+}
+
+fn read_array<R: Read + ?Sized, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
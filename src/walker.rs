@@ -0,0 +1,350 @@
+use std::path::{Path, PathBuf};
+
+use kernel::{
+    BlockTreeEntry, ChainType, ChainstateManager, CoinRef, Context, ContextBuilder,
+    core::{
+        BlockSpentOutputsExt, CoinExt, ScriptPubkeyExt, TransactionExt, TransactionSpentOutputsExt,
+        TxInExt, TxOutExt, TxOutPointExt, TxidExt,
+    },
+};
+
+use crate::{
+    CacheReader, CheckpointScope, Checkpointed, Config, Error, HeightRange, HintsBuilder, Report,
+    Result, analysis::Analysis, load_checkpoint, network_name,
+};
+
+/// A coin consumed by an input, as recorded in the undo data of the spending block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpentCoin {
+    pub confirmation_height: u32,
+    pub is_coinbase: bool,
+    pub value: i64,
+    pub script_pubkey: Vec<u8>,
+}
+
+impl From<CoinRef<'_>> for SpentCoin {
+    fn from(coin: CoinRef<'_>) -> Self {
+        let tx_out = coin.output();
+        Self {
+            confirmation_height: coin.confirmation_height(),
+            is_coinbase: coin.is_coinbase(),
+            value: tx_out.value(),
+            script_pubkey: tx_out.script_pubkey().to_bytes(),
+        }
+    }
+}
+
+/// All coins spent by the transactions of the block at `height`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpentBlock {
+    pub height: u32,
+    pub coins: Vec<SpentCoin>,
+}
+
+/// An analysis over the coins spent in each block.
+pub trait SpentCoinVisitor {
+    /// Called once for every block, before any of its coins are visited.
+    fn visit_block(&mut self, _height: u32) {}
+
+    /// Called for every coin spent by the block at `height`.
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin);
+}
+
+/// Combine partial results computed over disjoint height ranges. `other` always covers heights
+/// above those already merged into `self`.
+pub trait Merge {
+    fn merge(&mut self, other: Self);
+}
+
+/// Feed the `blocks` within `range` to the `visitor` in order, stopping at the first block that
+/// could not be read.
+pub fn walk<V: SpentCoinVisitor + ?Sized>(
+    blocks: impl IntoIterator<Item = Result<SpentBlock>>,
+    range: HeightRange,
+    visitor: &mut V,
+) -> Result<()> {
+    for block in blocks {
+        let block = block?;
+        if range.is_past(block.height) {
+            break;
+        }
+        if !range.contains(block.height) {
+            continue;
+        }
+        println!("Block at height {}", block.height);
+        visitor.visit_block(block.height);
+        for coin in &block.coins {
+            visitor.visit_coin(block.height, coin);
+        }
+    }
+    Ok(())
+}
+
+/// Visit each of the `ranges` on its own thread with a fresh visitor from `make_visitor`, then
+/// merge the partial results in height order. `blocks` yields the blocks of a single range.
+pub fn walk_parallel<V, I>(
+    ranges: &[HeightRange],
+    make_visitor: impl Fn() -> V + Sync,
+    blocks: impl Fn(HeightRange) -> I + Sync,
+) -> Result<V>
+where
+    V: SpentCoinVisitor + Merge + Send,
+    I: IntoIterator<Item = Result<SpentBlock>>,
+{
+    let partials = std::thread::scope(|scope| {
+        let handles = ranges
+            .iter()
+            .map(|&range| {
+                let make_visitor = &make_visitor;
+                let blocks = &blocks;
+                scope.spawn(move || {
+                    let mut visitor = make_visitor();
+                    walk(blocks(range), range, &mut visitor)?;
+                    Ok(visitor)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("worker thread panicked"))
+            .collect::<Result<Vec<_>>>()
+    })?;
+    let mut partials = partials.into_iter();
+    let mut visitor = partials.next().unwrap_or_else(make_visitor);
+    for partial in partials {
+        visitor.merge(partial);
+    }
+    Ok(visitor)
+}
+
+/// A store of spent coins, indexed by the height of the spending block.
+pub trait BlockSource: Sync {
+    fn network(&self) -> ChainType;
+
+    /// The height of the last block available.
+    fn tip(&self) -> u32;
+
+    /// Iterate over blocks in ascending height order, beginning at `start`.
+    fn blocks_from(&self, start: u32) -> Box<dyn Iterator<Item = Result<SpentBlock>> + '_>;
+}
+
+/// Reads the undo data of the active chain from a Bitcoin Core data directory.
+pub struct Chainstate {
+    network: ChainType,
+    chainman: ChainstateManager,
+    _context: Context,
+}
+
+impl Chainstate {
+    pub fn open(data_dir: &Path, network: ChainType) -> Result<Self> {
+        println!(
+            "Using directory {} on {}",
+            data_dir.display(),
+            network_name(network)
+        );
+        let blocks_dir = data_dir.join("blocks");
+        if !blocks_dir.is_dir() {
+            return Err(Error::Config(format!(
+                "{} is not a Bitcoin Core data directory for {}, it has no `blocks` directory",
+                data_dir.display(),
+                network_name(network)
+            )));
+        }
+        let (Some(data_dir), Some(blocks_dir)) = (data_dir.to_str(), blocks_dir.to_str()) else {
+            return Err(Error::Config(format!(
+                "{} is not a UTF-8 path",
+                data_dir.display()
+            )));
+        };
+        let context = ContextBuilder::new().chain_type(network).build()?;
+        let chainman = ChainstateManager::new(&context, data_dir, blocks_dir)?;
+        chainman.import_blocks()?;
+        Ok(Self {
+            network,
+            chainman,
+            _context: context,
+        })
+    }
+
+    fn read_block(&self, entry: BlockTreeEntry) -> Result<SpentBlock> {
+        let height = entry.height() as u32;
+        let undo = self.chainman.read_spent_outputs(&entry)?;
+        let mut coins = Vec::new();
+        for transaction in undo.iter() {
+            for coin in transaction.coins() {
+                coins.push(SpentCoin::from(coin));
+            }
+        }
+        Ok(SpentBlock { height, coins })
+    }
+
+    /// Connect the blocks from height 1 through `stop` to find the outputs unspent at `stop`.
+    pub fn hints(&self, stop: u32) -> Result<HintsBuilder> {
+        let chain = self.chainman.active_chain();
+        let mut hints = HintsBuilder::new();
+        for height in 1..=stop {
+            let entry = chain.at_height(height as usize).ok_or_else(|| {
+                Error::Config(format!(
+                    "stop height {stop} is past the tip at {}",
+                    chain.height()
+                ))
+            })?;
+            println!("Block at height {height}");
+            let block = self.chainman.read_block_data(&entry)?;
+            for transaction in block.transactions() {
+                let inputs = transaction.inputs().map(|input| {
+                    let outpoint = input.outpoint();
+                    (outpoint.txid().to_bytes(), outpoint.index())
+                });
+                let scripts: Vec<_> = transaction
+                    .outputs()
+                    .map(|output| output.script_pubkey().to_bytes())
+                    .collect();
+                hints.connect_transaction(
+                    height,
+                    transaction.txid().to_bytes(),
+                    inputs,
+                    scripts.iter().map(Vec::as_slice),
+                );
+            }
+        }
+        Ok(hints)
+    }
+}
+
+impl BlockSource for Chainstate {
+    fn network(&self) -> ChainType {
+        self.network
+    }
+
+    fn tip(&self) -> u32 {
+        self.chainman.active_chain().height().max(0) as u32
+    }
+
+    fn blocks_from(&self, start: u32) -> Box<dyn Iterator<Item = Result<SpentBlock>> + '_> {
+        let chain = self.chainman.active_chain();
+        Box::new(
+            (start as usize..)
+                .map_while(move |height| chain.at_height(height))
+                .map(|entry| self.read_block(entry)),
+        )
+    }
+}
+
+/// Drives analyses over the configured height range of a [`BlockSource`], either a Bitcoin Core
+/// data directory or a spent-coin cache file.
+pub struct ChainWalker {
+    range: HeightRange,
+    threads: usize,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: u32,
+    resume: bool,
+    source: Box<dyn BlockSource>,
+}
+
+impl ChainWalker {
+    pub fn new(config: &Config) -> Result<Self> {
+        let source: Box<dyn BlockSource> = match &config.cache {
+            Some(path) => {
+                println!("Using spent-coin cache {}", path.display());
+                Box::new(CacheReader::open(path)?)
+            }
+            None => {
+                let data_dir = config.network_dir().ok_or_else(|| {
+                    Error::Config("no data directory, set `BITCOIN_DIR` or pass `--datadir`".into())
+                })?;
+                Box::new(Chainstate::open(&data_dir, config.network)?)
+            }
+        };
+        Ok(Self::with_source(config, source))
+    }
+
+    pub fn with_source(config: &Config, source: Box<dyn BlockSource>) -> Self {
+        Self {
+            range: config.range,
+            threads: config.threads,
+            checkpoint: config.checkpoint.clone(),
+            checkpoint_interval: config.checkpoint_interval,
+            resume: config.resume,
+            source,
+        }
+    }
+
+    pub fn range(&self) -> HeightRange {
+        self.range
+    }
+
+    pub fn network(&self) -> ChainType {
+        self.source.network()
+    }
+
+    /// Describe a scan of the configured range, with its stop clamped to the tip of the source.
+    pub fn report<T>(&self, results: T) -> Report<T> {
+        let tip = self.source.tip();
+        Report {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            network: network_name(self.network()).to_string(),
+            start_height: self.range.start,
+            stop_height: self.range.stop.map_or(tip, |stop| stop.min(tip)),
+            results,
+        }
+    }
+
+    /// Iterate over the spent coins of every block in the source, from the start of the
+    /// configured range to the tip.
+    pub fn blocks(&self) -> impl Iterator<Item = Result<SpentBlock>> + '_ {
+        self.source.blocks_from(self.range.start)
+    }
+
+    /// Visit every block within the configured range.
+    pub fn walk<V: SpentCoinVisitor + ?Sized>(&self, visitor: &mut V) -> Result<()> {
+        walk(self.blocks(), self.range, visitor)
+    }
+
+    /// Visit every block of the configured range, split across the configured number of
+    /// threads. The result is identical to a sequential [`walk`](Self::walk) over one visitor.
+    ///
+    /// When a checkpoint file is configured the scan is sequential, saving the visitor state
+    /// periodically and optionally resuming from a previous run of the analysis with the same
+    /// [`name`](Analysis::name).
+    pub fn scan<V>(&self, make_visitor: impl Fn() -> V + Sync) -> Result<V>
+    where
+        V: Analysis + Merge,
+    {
+        if let Some(path) = &self.checkpoint {
+            let mut visitor = make_visitor();
+            let scope = CheckpointScope {
+                analysis: visitor.name().to_string(),
+                network: network_name(self.network()).to_string(),
+                start_height: self.range.start,
+                stop_height: self.range.stop,
+            };
+            let mut range = self.range;
+            if self.resume {
+                let height = load_checkpoint(path, &scope, &mut visitor)?;
+                println!("Resuming from checkpoint at height {height}");
+                range.start = range.start.max(height + 1);
+            }
+            let mut checkpointed =
+                Checkpointed::new(&mut visitor, path.clone(), scope, self.checkpoint_interval);
+            // Save the progress made before a block could not be read, then report the failure.
+            let walked = walk(
+                self.source.blocks_from(range.start),
+                range,
+                &mut checkpointed,
+            );
+            checkpointed.finish()?;
+            walked?;
+            return Ok(visitor);
+        }
+        if self.threads <= 1 {
+            let mut visitor = make_visitor();
+            self.walk(&mut visitor)?;
+            return Ok(visitor);
+        }
+        let ranges = self.range.split(self.source.tip(), self.threads);
+        walk_parallel(&ranges, make_visitor, |range| {
+            self.source.blocks_from(range.start)
+        })
+    }
+}
@@ -0,0 +1,110 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use kernel::ChainType;
+use swiftsync_research::{CacheWriter, Report};
+
+mod support;
+
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ages-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn inputs(dir: &PathBuf, table: &str) -> String {
+    fs::write(dir.join("input_ages.csv"), table).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_swiftsync"))
+        .args(["ages", "inputs", "--dir"])
+        .arg(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn short_chains_have_no_input_summary() {
+    let dir = dir("short");
+    let stdout = inputs(&dir, "block,ages\n1,\n2,1|1\n");
+    assert!(
+        stdout.contains("percentage 5 blocks or earlier: no data"),
+        "{stdout}"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn input_summary_takes_the_sorted_median() {
+    let dir = dir("median");
+    let stdout = inputs(
+        &dir,
+        "block,ages\n1,1\n100000,1|1|1|1000\n100001,1000\n100002,1|1000\n",
+    );
+    // The blocks have 3/4, 0 and 1/2 of their inputs at most 5 blocks old.
+    assert!(
+        stdout.contains("percentage 5 blocks or earlier 0.4167, median: 0.5000"),
+        "{stdout}"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_first_row_is_read() {
+    let dir = dir("first");
+    let stdout = inputs(&dir, "block,ages\n100000,1\n");
+    assert!(
+        stdout.contains("percentage 5 blocks or earlier 1.0000, median: 1.0000"),
+        "{stdout}"
+    );
+    fs::write(dir.join("counts.csv"), "age,count\n1,7\n2,3\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_swiftsync"))
+        .args(["ages", "plot", "--dir"])
+        .arg(&dir)
+        .arg("--output")
+        .arg(dir.join("plot.png"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Coin age 1, number of occurrences 7"),
+        "{stdout}"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn all_analyses_may_include_coinbase_ages() {
+    let dir = dir("all");
+    let cache = dir.join("regtest.cache");
+    let mut writer = CacheWriter::create(&cache, ChainType::Regtest).unwrap();
+    for block in support::expected_spent_blocks() {
+        writer.write_block(&block).unwrap();
+    }
+    writer.finish().unwrap();
+    let ages = |flags: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_swiftsync"))
+            .args(["all", "--network", "regtest", "--cache"])
+            .arg(&cache)
+            .arg("--dir")
+            .arg(&dir)
+            .arg("--json")
+            .arg(dir.join("all.json"))
+            .args(flags)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let report: Report =
+            serde_json::from_slice(&fs::read(dir.join("all.json")).unwrap()).unwrap();
+        report.results["ages"].clone()
+    };
+    let without = ages(&[]);
+    let with = ages(&["--include-coinbase"]);
+    assert_eq!(without["include_coinbase"], false);
+    assert_eq!(with["include_coinbase"], true);
+    // Only the funding block spends a coinbase output.
+    assert_eq!(
+        with["coins"].as_u64().unwrap(),
+        without["coins"].as_u64().unwrap() + 1
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,413 @@
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use kernel::ChainType;
+use serde_json::Value;
+use swiftsync_research::{
+    BlockSource, ChainWalker, Checkpoint, Config, HeightRange, Merge, Report, Result, SpentBlock,
+    SpentCoin, SpentCoinVisitor,
+    analysis::{
+        AmountSavings, AmountSummary, Analyses, Analysis, BareMultisig, BareMultisigSummary,
+        CoinAges, Entropy, EntropyCoding, EntropyCodingSummary, FieldEntropy, HeightEncodings,
+        HeightEncodingsSummary, ScriptSavings, ScriptTypeCount, ScriptTypes, ScriptTypesSummary,
+        UncompressedP2pk,
+    },
+    script::ScriptType,
+    walk,
+};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Counter {
+    #[serde(skip)]
+    coins: Arc<AtomicU64>,
+}
+
+impl SpentCoinVisitor for Counter {
+    fn visit_coin(&mut self, _height: u32, _coin: &SpentCoin) {
+        self.coins.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Merge for Counter {
+    fn merge(&mut self, _other: Self) {}
+}
+
+impl Analysis for Counter {
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn results(&self) -> Value {
+        Value::from(self.coins.load(Ordering::Relaxed))
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn every_analysis_sees_every_coin() {
+    let first = Arc::new(AtomicU64::new(0));
+    let second = Arc::new(AtomicU64::new(0));
+    let mut analyses = Analyses::new();
+    analyses.register(Counter {
+        coins: Arc::clone(&first),
+    });
+    analyses.register(Counter {
+        coins: Arc::clone(&second),
+    });
+    let blocks = (1..=3).map(|height| SpentBlock {
+        height,
+        coins: vec![
+            SpentCoin {
+                confirmation_height: 0,
+                is_coinbase: true,
+                value: 5_000_000_000,
+                script_pubkey: vec![0x51],
+            };
+            height as usize
+        ],
+    });
+    walk(blocks.map(Ok), HeightRange::default(), &mut analyses).unwrap();
+    assert_eq!(first.load(Ordering::Relaxed), 6);
+    assert_eq!(second.load(Ordering::Relaxed), 6);
+}
+
+struct Blocks(Vec<SpentBlock>);
+
+impl BlockSource for Blocks {
+    fn network(&self) -> ChainType {
+        ChainType::Regtest
+    }
+
+    fn tip(&self) -> u32 {
+        self.0.last().map_or(0, |block| block.height)
+    }
+
+    fn blocks_from(&self, start: u32) -> Box<dyn Iterator<Item = Result<SpentBlock>> + '_> {
+        Box::new(self.0.iter().skip(start as usize).cloned().map(Ok))
+    }
+}
+
+fn coin(confirmation_height: u32, value: i64, script_pubkey: Vec<u8>) -> SpentCoin {
+    SpentCoin {
+        confirmation_height,
+        is_coinbase: false,
+        value,
+        script_pubkey,
+    }
+}
+
+#[test]
+fn reports_exact_results() {
+    let p2wpkh = [vec![0x00, 0x14], vec![0; 20]].concat();
+    // The uncompressed generator point.
+    let p2pk_uncompressed = [
+        &[65, 0x04][..],
+        &[
+            0x79, 0xBE, 0x66, 0x7E, 0xF9, 0xDC, 0xBB, 0xAC, 0x55, 0xA0, 0x62, 0x95, 0xCE, 0x87,
+            0x0B, 0x07, 0x02, 0x9B, 0xFC, 0xDB, 0x2D, 0xCE, 0x28, 0xD9, 0x59, 0xF2, 0x81, 0x5B,
+            0x16, 0xF8, 0x17, 0x98, 0x48, 0x3A, 0xDA, 0x77, 0x26, 0xA3, 0xC4, 0x65, 0x5D, 0xA4,
+            0xFB, 0xFC, 0x0E, 0x11, 0x08, 0xA8, 0xFD, 0x17, 0xB4, 0x48, 0xA6, 0x85, 0x54, 0x19,
+            0x9C, 0x47, 0xD0, 0x8F, 0xFB, 0x10, 0xD4, 0xB8,
+        ],
+        &[0xAC],
+    ]
+    .concat();
+    let blocks = (0..10)
+        .map(|height| SpentBlock {
+            height,
+            coins: if height == 0 {
+                Vec::new()
+            } else {
+                vec![
+                    coin(height - 1, 100_000_000, p2wpkh.clone()),
+                    coin(0, 1, p2pk_uncompressed.clone()),
+                ]
+            },
+        })
+        .collect();
+    let mut config = Config::new(ChainType::Regtest);
+    config.range = HeightRange::new(2, Some(1_000));
+    config.threads = 3;
+    let walker = ChainWalker::with_source(&config, Box::new(Blocks(blocks)));
+    let analyses = walker
+        .scan(|| {
+            let mut analyses = Analyses::new();
+            analyses.register(AmountSavings::default());
+            analyses.register(ScriptSavings::default());
+            analyses.register(UncompressedP2pk::default());
+            analyses.register(ScriptTypes::default());
+            analyses.register(CoinAges::new(false));
+            analyses
+        })
+        .unwrap();
+    let report = walker.report(analyses.results());
+    assert_eq!(report.network, "regtest");
+    assert_eq!((report.start_height, report.stop_height), (2, 9));
+    assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+
+    // 16 coins, each amount compresses to a single byte.
+    let amounts: AmountSummary = serde_json::from_value(report.results["amounts"].clone()).unwrap();
+    assert_eq!(
+        amounts,
+        AmountSummary {
+            coins: 16,
+            varint_savings_bytes: 16 * 7,
+            compact_size_savings_bytes: 16 * 7,
+        }
+    );
+    let scripts = &report.results["scripts"];
+    assert_eq!(scripts["savings_bytes"], 8 * 2 + 8 * 35);
+    assert_eq!(scripts["undo_bytes"], 8 * (12 + 22) + 8 * (12 + 67));
+    assert_eq!(report.results["p2pk"]["compressible"], 8);
+    assert_eq!(report.results["p2pk"]["savings_bytes"], 8 * 32);
+    let types: ScriptTypesSummary =
+        serde_json::from_value(report.results["script_types"].clone()).unwrap();
+    assert_eq!(types.script_bytes, 8 * (22 + 67));
+    assert_eq!(
+        types.types.into_iter().collect::<Vec<_>>(),
+        vec![
+            (
+                ScriptType::P2pkUncompressed,
+                ScriptTypeCount {
+                    coins: 8,
+                    script_bytes: 8 * 67,
+                }
+            ),
+            (
+                ScriptType::P2wpkh,
+                ScriptTypeCount {
+                    coins: 8,
+                    script_bytes: 8 * 22,
+                }
+            ),
+        ]
+    );
+    assert_eq!(report.results["ages"]["coins"], 16);
+    assert_eq!(report.results["ages"]["blocks"], 8);
+    assert_eq!(report.results["ages"]["max_age"], 9);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn reports_fail_when_they_cannot_be_flushed() {
+    let report = Report {
+        version: "0".into(),
+        network: "regtest".into(),
+        start_height: 0,
+        stop_height: 0,
+        results: Value::Null,
+    };
+    // Writes to /dev/full succeed until the buffered report is flushed.
+    let err = report.write_json(Path::new("/dev/full")).unwrap_err();
+    assert!(matches!(err, swiftsync_research::Error::Io { .. }), "{err}");
+}
+
+#[test]
+fn bare_multisig_savings() {
+    let key = |prefix: u8| [vec![33, prefix], vec![0x11; 32]].concat();
+    let one_of_two = [vec![0x51], key(0x02), key(0x03), vec![0x52, 0xAE]].concat();
+    let hybrid = [vec![0x51, 65, 0x06], vec![0x22; 64], vec![0x51, 0xAE]].concat();
+    let blocks = vec![
+        SpentBlock {
+            height: 0,
+            coins: Vec::new(),
+        },
+        SpentBlock {
+            height: 1,
+            coins: vec![
+                coin(0, 1, one_of_two.clone()),
+                coin(0, 1, one_of_two),
+                coin(0, 1, hybrid),
+                coin(0, 1, vec![0x51]),
+            ],
+        },
+    ];
+    let mut config = Config::new(ChainType::Regtest);
+    config.range = HeightRange::new(0, None);
+    let walker = ChainWalker::with_source(&config, Box::new(Blocks(blocks)));
+    let summary = walker.scan(BareMultisig::default).unwrap().summary();
+    // A 71 byte script with a 1 byte length becomes a tag, packed `m` and `n` and two keys.
+    assert_eq!(
+        summary,
+        BareMultisigSummary {
+            coins: 4,
+            multisig: 3,
+            compressible: 2,
+            raw: 1,
+            undo_bytes: 2 * (12 + 71) + 12 + 69,
+            script_bytes: 2 * 71 + 69,
+            savings_bytes: 2 * (72 - 68),
+        }
+    );
+}
+
+#[test]
+fn height_encodings() {
+    let coinbase = SpentCoin {
+        is_coinbase: true,
+        ..coin(100, 1, vec![0x51])
+    };
+    let blocks = vec![
+        SpentBlock {
+            height: 0,
+            coins: Vec::new(),
+        },
+        SpentBlock {
+            height: 70_000,
+            coins: vec![
+                coin(1, 1, vec![0x51]),
+                coin(69_990, 1, vec![0x51]),
+                coinbase,
+            ],
+        },
+    ];
+    let mut config = Config::new(ChainType::Regtest);
+    config.range = HeightRange::new(0, None);
+    let walker = ChainWalker::with_source(&config, Box::new(Blocks(blocks)));
+    let summary = walker.scan(HeightEncodings::default).unwrap().summary();
+    assert_eq!(
+        summary,
+        HeightEncodingsSummary {
+            coins: 3,
+            u32_bytes: 12,
+            varint_bytes: 1 + 3 + 1,
+            compact_size_bytes: 1 + 5 + 1,
+            height_coinbase_varint_bytes: 1 + 3 + 2,
+            age_varint_bytes: 3 + 1 + 3,
+            age_coinbase_varint_bytes: 3 + 1 + 3,
+        }
+    );
+}
+
+#[test]
+fn entropy_coding_codes_each_block_on_its_own() {
+    let p2pkh = [vec![0x76, 0xa9, 20], vec![0; 20], vec![0x88, 0xac]].concat();
+    let blocks: Vec<_> = (0..20)
+        .map(|height| SpentBlock {
+            height,
+            coins: if height % 2 == 0 {
+                Vec::new()
+            } else {
+                vec![coin(0, 100_000, p2pkh.clone()); 500]
+            },
+        })
+        .collect();
+    let scan = |threads| {
+        let mut config = Config::new(ChainType::Regtest);
+        config.range = HeightRange::new(0, None);
+        config.threads = threads;
+        let walker = ChainWalker::with_source(&config, Box::new(Blocks(blocks.clone())));
+        walker.scan(EntropyCoding::default).unwrap().summary()
+    };
+    let summary = scan(1);
+    assert_eq!(scan(4), summary);
+    // Repeated fields cost a fraction of their byte-aligned size, even coded block by block.
+    let EntropyCodingSummary {
+        coins,
+        amount_bytes,
+        amount_coded_bytes,
+        age_bytes,
+        age_coded_bytes,
+        tag_bytes,
+        tag_coded_bytes,
+    } = summary;
+    assert_eq!(coins, 5_000);
+    assert_eq!((amount_bytes, age_bytes, tag_bytes), (5_000, 5_000, 5_000));
+    for coded in [amount_coded_bytes, age_coded_bytes, tag_coded_bytes] {
+        assert!((10 * 4..1_000).contains(&coded), "{coded}");
+    }
+}
+
+#[test]
+fn empirical_entropy_of_each_field() {
+    let p2pkh = [vec![0x76, 0xa9, 20], vec![0; 20], vec![0x88, 0xac]].concat();
+    let p2wpkh = [vec![0x00, 0x14], vec![0; 20]].concat();
+    let blocks = vec![
+        SpentBlock {
+            height: 0,
+            coins: Vec::new(),
+        },
+        SpentBlock {
+            height: 10,
+            coins: vec![
+                coin(9, 100_000, p2pkh.clone()),
+                coin(8, 100_000, p2pkh),
+                coin(9, 546, p2wpkh.clone()),
+                coin(9, 546, p2wpkh),
+            ],
+        },
+    ];
+    let mut config = Config::new(ChainType::Regtest);
+    config.range = HeightRange::new(0, None);
+    let walker = ChainWalker::with_source(&config, Box::new(Blocks(blocks)));
+    let entropy = walker.scan(Entropy::default).unwrap();
+    let mut restored = Entropy::default();
+    let mut saved = Vec::new();
+    entropy.save(&mut saved).unwrap();
+    restored.restore(&mut saved.as_slice()).unwrap();
+    let summary = restored.summary();
+    assert_eq!(summary, entropy.summary());
+    assert_eq!(summary.coins, 4);
+    // Each script type has a single amount and script length: one bit per coin that the type
+    // already tells apart.
+    let one_bit = |varint_bytes, compact_size_bytes| FieldEntropy {
+        symbols: 2,
+        entropy_bits: 1.,
+        floor_bytes: 1,
+        given_script_type_bits: 0.,
+        given_script_type_floor_bytes: 0,
+        varint_bytes,
+        compact_size_bytes,
+    };
+    // 100,000 compresses to 6 and 546 to 4911.
+    assert_eq!(summary.amounts, one_bit(2 + 2 * 2, 2 + 2 * 3));
+    assert_eq!(summary.script_types, one_bit(4, 4));
+    assert_eq!(summary.script_lengths, one_bit(4, 4));
+    // Three ages of 1 and one of 2, of which only the P2PKH coins differ.
+    let ages = summary.ages;
+    assert_eq!(ages.symbols, 2);
+    assert!((ages.entropy_bits - (2. - 0.75 * 3f64.log2())).abs() < 1e-9);
+    assert_eq!(ages.floor_bytes, 1);
+    assert_eq!(ages.given_script_type_bits, 0.5);
+    assert_eq!(ages.given_script_type_floor_bytes, 1);
+    assert_eq!((ages.varint_bytes, ages.compact_size_bytes), (4, 4));
+}
+
+#[test]
+fn amount_savings_of_a_range_can_be_a_loss() {
+    let script = vec![0x51];
+    // Both take 9 byte `CompactSize`s, the second an 8 byte `VarInt`.
+    let mut large = AmountSavings::default();
+    large.visit_coin(1, &coin(0, 1_234_567_891, script.clone()));
+    large.visit_coin(1, &coin(0, 2_099_999_999_999_999, script.clone()));
+    assert_eq!(
+        large.summary(),
+        AmountSummary {
+            coins: 2,
+            varint_savings_bytes: 3,
+            compact_size_savings_bytes: -2,
+        }
+    );
+    let mut small = AmountSavings::default();
+    small.visit_coin(2, &coin(0, 1, script));
+    large.merge(small);
+    assert_eq!(
+        large.summary(),
+        AmountSummary {
+            coins: 3,
+            varint_savings_bytes: 10,
+            compact_size_savings_bytes: 5,
+        }
+    );
+}
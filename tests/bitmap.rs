@@ -0,0 +1,112 @@
+use std::io::{self, ErrorKind};
+
+use swiftsync_research::{bitmap, write_varint};
+
+type Encode = fn(&[u32]) -> Vec<u8>;
+type Decode = fn(&[u8]) -> io::Result<(Vec<u32>, usize)>;
+
+const CODECS: [(Encode, Decode); 3] = [
+    (bitmap::encode, bitmap::decode),
+    (bitmap::encode_runs, bitmap::decode_runs),
+    (bitmap::encode_roaring, bitmap::decode_roaring),
+];
+
+fn round_trip(indices: &[u32]) {
+    for (encode, decode) in CODECS {
+        let bytes = encode(indices);
+        assert_eq!(decode(&bytes).unwrap(), (indices.to_vec(), bytes.len()));
+    }
+}
+
+#[test]
+fn serializes_bitmaps() {
+    // Ten bits, least significant first.
+    assert_eq!(bitmap::encode(&[3, 4, 9]), vec![0x0a, 0x18, 0x02]);
+    assert_eq!(bitmap::encode(&[]), vec![0x00]);
+    // Three unset, two set, four unset and one set bit.
+    assert_eq!(bitmap::encode_runs(&[3, 4, 9]), vec![0x04, 3, 2, 4, 1]);
+    assert_eq!(bitmap::encode_runs(&[0, 1]), vec![0x02, 0, 2]);
+    assert_eq!(bitmap::encode_runs(&[]), vec![0x00]);
+}
+
+#[test]
+fn roaring_containers_take_the_smallest_form() {
+    // An array of three lower halves is smaller than two runs.
+    assert_eq!(
+        bitmap::encode_roaring(&[3, 4, 9]),
+        vec![0x01, 0, 0, 0, 0x03, 3, 0, 4, 0, 9, 0]
+    );
+    // A single run of 100 indices starting at 0.
+    assert_eq!(
+        bitmap::encode_roaring(&(0..100).collect::<Vec<_>>()),
+        vec![0x01, 0, 0, 2, 0x01, 0, 0, 99, 0]
+    );
+    assert_eq!(bitmap::encode_roaring(&[]), vec![0x00]);
+    // Every other index of a container is too many for an array and too many runs.
+    let dense: Vec<u32> = (0..1 << 16).step_by(2).collect();
+    let bytes = bitmap::encode_roaring(&dense);
+    assert_eq!(bytes.len(), 1 + 2 + 1 + 8192);
+    assert_eq!(bytes[3], 1);
+    assert!(bytes[4..].iter().all(|&byte| byte == 0x55));
+    // Keys are the upper halves of the indices.
+    assert_eq!(
+        bitmap::encode_roaring(&[5, 1 << 16 | 7]),
+        vec![0x02, 0, 0, 0, 0x01, 5, 0, 1, 0, 0, 0x01, 7, 0]
+    );
+}
+
+#[test]
+fn indices_round_trip() {
+    round_trip(&[]);
+    round_trip(&[0]);
+    round_trip(&[7]);
+    round_trip(&[3, 4, 9]);
+    round_trip(&(0..1_000).collect::<Vec<_>>());
+    round_trip(&(0..1_000).map(|i| i * 300 + i % 7).collect::<Vec<_>>());
+    round_trip(&(0..200_000).filter(|i| i % 3 != 1).collect::<Vec<_>>());
+    round_trip(&(0..1 << 17).step_by(2).collect::<Vec<_>>());
+    // Only the raw bitmap has to spell out every bit up to the largest index.
+    let sparse = [0, 1 << 20, u32::MAX];
+    for (encode, decode) in &CODECS[1..] {
+        let bytes = encode(&sparse);
+        assert_eq!(decode(&bytes).unwrap(), (sparse.to_vec(), bytes.len()));
+    }
+}
+
+#[test]
+fn malformed_input_is_rejected() {
+    for (encode, decode) in CODECS {
+        let bytes = encode(&[3, 4, 9, 1 << 16]);
+        for len in 0..bytes.len() {
+            let err = decode(&bytes[..len]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{len}");
+        }
+    }
+    // Runs that pass the largest index.
+    let mut runs = vec![0x02];
+    write_varint(&mut runs, u32::MAX as u64).unwrap();
+    write_varint(&mut runs, 2).unwrap();
+    let err = bitmap::decode_runs(&runs).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = bitmap::decode(&[0xff, 0, 0, 0, 0, 0x02, 0, 0, 0]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    for bytes in [
+        // An unknown container type.
+        &[0x01, 0, 0, 3][..],
+        // A run past the end of its container.
+        &[0x01, 0, 0, 2, 0x01, 0xff, 0xff, 1, 0],
+        // Containers out of order.
+        &[0x02, 1, 0, 0, 0x01, 0, 0, 0, 0, 0, 0x01, 0, 0],
+        // An array that is not sorted.
+        &[0x01, 0, 0, 0, 0x02, 4, 0, 3, 0],
+    ] {
+        let err = bitmap::decode_roaring(bytes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{bytes:?}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "strictly increasing")]
+fn indices_must_increase() {
+    bitmap::encode_roaring(&[4, 3]);
+}
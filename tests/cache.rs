@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+
+use kernel::ChainType;
+use serde_json::Value;
+use swiftsync_research::{
+    BlockSource, CacheReader, CacheWriter, ChainWalker, Config, HeightRange, Merge, Result,
+    SpentBlock, SpentCoin, SpentCoinVisitor, analysis::Analysis, walk,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}.cache", std::process::id()))
+}
+
+fn synthetic_chain() -> Vec<SpentBlock> {
+    (0..40u32)
+        .map(|height| SpentBlock {
+            height,
+            coins: (0..height % 11)
+                .map(|i| SpentCoin {
+                    confirmation_height: height - (i * 3).min(height),
+                    is_coinbase: i % 4 == 0,
+                    value: (height as i64) * 100_000_000 + i as i64,
+                    script_pubkey: vec![0xAB; (i * 7 % 70) as usize],
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Totals {
+    blocks: Vec<u32>,
+    value: i64,
+    coinbase: u64,
+    script_bytes: usize,
+}
+
+impl SpentCoinVisitor for Totals {
+    fn visit_block(&mut self, height: u32) {
+        self.blocks.push(height);
+    }
+
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.value += coin.value;
+        self.coinbase += coin.is_coinbase as u64;
+        self.script_bytes += coin.script_pubkey.len();
+    }
+}
+
+impl Analysis for Totals {
+    fn name(&self) -> &'static str {
+        "totals"
+    }
+
+    fn results(&self) -> Value {
+        serde_json::to_value(self).unwrap()
+    }
+
+    fn report(&self, _out_dir: &Path) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Merge for Totals {
+    fn merge(&mut self, other: Self) {
+        self.blocks.extend(other.blocks);
+        self.value += other.value;
+        self.coinbase += other.coinbase;
+        self.script_bytes += other.script_bytes;
+    }
+}
+
+#[test]
+fn round_trip() {
+    let path = temp_path("round-trip");
+    let chain = synthetic_chain();
+    let mut writer = CacheWriter::create(&path, ChainType::Signet).unwrap();
+    walk(
+        chain.iter().cloned().map(Ok),
+        HeightRange::new(0, None),
+        &mut writer,
+    )
+    .unwrap();
+    writer.finish().unwrap();
+
+    let reader = CacheReader::open(&path).unwrap();
+    assert_eq!(reader.network(), ChainType::Signet);
+    assert_eq!(reader.heights(), Some((0, 39)));
+    assert_eq!(reader.tip(), 39);
+    let blocks = |start| {
+        reader
+            .blocks_from(start)
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+    };
+    assert_eq!(blocks(0), chain);
+    assert_eq!(blocks(17), chain[17..]);
+    assert_eq!(reader.blocks_from(40).count(), 0);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn scan_from_cache_matches_source() {
+    let path = temp_path("scan");
+    let chain = synthetic_chain();
+    let mut writer = CacheWriter::create(&path, ChainType::Regtest).unwrap();
+    for block in &chain {
+        writer.write_block(block).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let range = HeightRange::new(5, Some(33));
+    let mut want = Totals::default();
+    walk(chain.into_iter().map(Ok), range, &mut want).unwrap();
+    let mut config = Config::new(ChainType::Regtest);
+    config.range = range;
+    config.cache = Some(path.clone());
+    for threads in [1, 4] {
+        config.threads = threads;
+        let walker = ChainWalker::new(&config).unwrap();
+        assert_eq!(walker.scan(Totals::default).unwrap(), want);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn checkpoints_name_the_analysis() {
+    let path = temp_path("checkpointed");
+    let checkpoint = temp_path("checkpointed-scan");
+    let chain = synthetic_chain();
+    let mut writer = CacheWriter::create(&path, ChainType::Regtest).unwrap();
+    for block in &chain {
+        writer.write_block(block).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let range = HeightRange::new(5, Some(33));
+    let mut want = Totals::default();
+    walk(chain.into_iter().map(Ok), range, &mut want).unwrap();
+    let mut config = Config::new(ChainType::Regtest);
+    config.range = range;
+    config.cache = Some(path.clone());
+    config.checkpoint = Some(checkpoint.clone());
+    config.checkpoint_interval = 10;
+    assert_eq!(
+        ChainWalker::new(&config)
+            .unwrap()
+            .scan(Totals::default)
+            .unwrap(),
+        want
+    );
+    let saved = std::fs::read_to_string(&checkpoint).unwrap();
+    let header: Value = serde_json::from_str(saved.lines().next().unwrap()).unwrap();
+    assert_eq!(header["analysis"], "totals");
+    config.resume = true;
+    assert_eq!(
+        ChainWalker::new(&config)
+            .unwrap()
+            .scan(Totals::default)
+            .unwrap(),
+        want
+    );
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(checkpoint).unwrap();
+}
+
+#[test]
+fn rejects_unknown_version() {
+    let path = temp_path("version");
+    let writer = CacheWriter::create(&path, ChainType::Mainnet).unwrap();
+    writer.finish().unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[4] = 0xFF;
+    std::fs::write(&path, bytes).unwrap();
+    assert!(CacheReader::open(&path).is_err());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_out_of_order_blocks() {
+    let mut writer = CacheWriter::new(Vec::new(), ChainType::Mainnet).unwrap();
+    let block = SpentBlock {
+        height: 10,
+        coins: Vec::new(),
+    };
+    writer.write_block(&block).unwrap();
+    assert!(writer.write_block(&block).is_err());
+}
+
+#[test]
+fn rejects_corrupt_lengths() {
+    let path = temp_path("corrupt");
+    let mut writer = CacheWriter::create(&path, ChainType::Regtest).unwrap();
+    writer
+        .write_block(&SpentBlock {
+            height: 1,
+            coins: vec![SpentCoin {
+                confirmation_height: 0,
+                is_coinbase: true,
+                value: 5,
+                script_pubkey: vec![0x51],
+            }],
+        })
+        .unwrap();
+    writer.finish().unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let footer = bytes.len() - 16;
+    // Block counts and index offsets that do not fit the file, before allocating the index.
+    for (offset, field) in [
+        (footer, &u32::MAX.to_le_bytes()[..]),
+        (footer + 4, &2u64.to_le_bytes()),
+        (footer + 4, &u64::MAX.to_le_bytes()),
+    ] {
+        let mut corrupt = bytes.clone();
+        corrupt[offset..offset + field.len()].copy_from_slice(field);
+        std::fs::write(&path, corrupt).unwrap();
+        let err = CacheReader::open(&path)
+            .err()
+            .expect("the index is rejected");
+        assert!(err.to_string().contains("does not fit"), "{err}");
+    }
+    // The coin count and the script length of the block: height, count, age, coinbase bitmap,
+    // value and script length each take a byte after the 6 byte header.
+    for (offset, what) in [(7, "coin count"), (11, "script length")] {
+        let mut corrupt = bytes.clone();
+        corrupt[offset] = 0x7f;
+        std::fs::write(&path, corrupt).unwrap();
+        let reader = CacheReader::open(&path).unwrap();
+        let err = reader.blocks_from(0).next().unwrap().unwrap_err();
+        assert!(err.to_string().contains(what), "{err}");
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_blocks_the_index_does_not_point_to() {
+    let path = temp_path("spliced");
+    let mut writer = CacheWriter::create(&path, ChainType::Regtest).unwrap();
+    for height in [1, 2] {
+        writer
+            .write_block(&SpentBlock {
+                height,
+                coins: Vec::new(),
+            })
+            .unwrap();
+    }
+    writer.finish().unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let index = bytes.len() - 16 - 2 * 12;
+    // The height of the first block, the height of its index entry and the offset of the second.
+    for (offset, field, what) in [
+        (6, &[3][..], "block 3 is indexed at height 1"),
+        (index, &3u32.to_le_bytes(), "block 1 is indexed at height 3"),
+        (index + 16, &9u64.to_le_bytes(), "does not start where"),
+    ] {
+        let mut corrupt = bytes.clone();
+        corrupt[offset..offset + field.len()].copy_from_slice(field);
+        std::fs::write(&path, corrupt).unwrap();
+        let reader = CacheReader::open(&path).unwrap();
+        let err = reader
+            .blocks_from(0)
+            .find_map(Result::err)
+            .expect("a block is rejected");
+        assert!(err.to_string().contains(what), "{err}");
+    }
+    std::fs::remove_file(path).unwrap();
+}
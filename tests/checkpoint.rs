@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use swiftsync_research::{
+    CheckpointScope, Checkpointed, Error, HeightRange, Result, SpentBlock, SpentCoin,
+    SpentCoinVisitor,
+    analysis::{AmountSavings, Analyses, Analysis, ScriptTypes},
+    load_checkpoint, save_checkpoint, walk,
+};
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Totals {
+    blocks: u64,
+    value: i64,
+    last: Option<u32>,
+}
+
+impl SpentCoinVisitor for Totals {
+    fn visit_block(&mut self, height: u32) {
+        self.blocks += 1;
+        self.last = Some(height);
+    }
+
+    fn visit_coin(&mut self, _height: u32, coin: &SpentCoin) {
+        self.value += coin.value;
+    }
+}
+
+fn scope(analysis: &str) -> CheckpointScope {
+    CheckpointScope {
+        analysis: analysis.to_string(),
+        network: "regtest".to_string(),
+        start_height: 0,
+        stop_height: None,
+    }
+}
+
+fn chain() -> impl Iterator<Item = Result<SpentBlock>> {
+    (0..50)
+        .map(|height| SpentBlock {
+            height,
+            coins: vec![
+                SpentCoin {
+                    confirmation_height: 0,
+                    is_coinbase: false,
+                    value: height as i64,
+                    script_pubkey: Vec::new(),
+                };
+                2
+            ],
+        })
+        .map(Ok)
+}
+
+#[test]
+fn resume_matches_uninterrupted_scan() {
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.json", std::process::id()));
+    let mut uninterrupted = Totals::default();
+    walk(chain(), HeightRange::new(0, None), &mut uninterrupted).unwrap();
+
+    // Crash somewhere after height 33, the last checkpoint covers heights 0 through 29.
+    let mut crashed = Totals::default();
+    let mut checkpointed = Checkpointed::new(&mut crashed, path.clone(), scope("totals"), 10);
+    walk(chain(), HeightRange::new(0, Some(33)), &mut checkpointed).unwrap();
+    drop(checkpointed);
+
+    let mut resumed = Totals::default();
+    let height = load_checkpoint(&path, &scope("totals"), &mut resumed).unwrap();
+    assert_eq!(height, 29);
+    assert_eq!(resumed.last, Some(29));
+    let mut checkpointed = Checkpointed::new(&mut resumed, path.clone(), scope("totals"), 10);
+    walk(
+        chain(),
+        HeightRange::new(height + 1, None),
+        &mut checkpointed,
+    )
+    .unwrap();
+    checkpointed.finish().unwrap();
+    assert_eq!(resumed, uninterrupted);
+
+    let mut reloaded = Totals::default();
+    assert_eq!(
+        load_checkpoint(&path, &scope("totals"), &mut reloaded).unwrap(),
+        49
+    );
+    assert_eq!(reloaded, uninterrupted);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn analyses_and_tmp_paths_round_trip() {
+    // The temporary file must not be the checkpoint itself.
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.tmp", std::process::id()));
+    let all = || {
+        let mut analyses = Analyses::new();
+        analyses.register(AmountSavings::default());
+        analyses.register(ScriptTypes::default());
+        analyses
+    };
+    let mut saved = all();
+    walk(chain(), HeightRange::new(0, None), &mut saved).unwrap();
+    save_checkpoint(&path, &scope("all"), 49, &saved).unwrap();
+    let mut restored = all();
+    assert_eq!(
+        load_checkpoint(&path, &scope("all"), &mut restored).unwrap(),
+        49
+    );
+    assert_eq!(restored.results(), saved.results());
+    // A checkpoint of one analysis does not restore two.
+    save_checkpoint(&path, &scope("all"), 49, &AmountSavings::default()).unwrap();
+    assert!(load_checkpoint(&path, &scope("all"), &mut all()).is_err());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn checkpoints_only_resume_their_own_scan() {
+    let path = std::env::temp_dir().join(format!("checkpoint-scope-{}.json", std::process::id()));
+    let saved = scope("totals");
+    save_checkpoint(&path, &saved, 9, &Totals::default()).unwrap();
+    let others = [
+        scope("amounts"),
+        CheckpointScope {
+            network: "signet".to_string(),
+            ..saved.clone()
+        },
+        CheckpointScope {
+            start_height: 1,
+            ..saved.clone()
+        },
+        CheckpointScope {
+            stop_height: Some(49),
+            ..saved.clone()
+        },
+    ];
+    for other in others {
+        let err = load_checkpoint(&path, &other, &mut Totals::default()).unwrap_err();
+        assert!(matches!(err, Error::Checkpoint { .. }), "{other}");
+        assert!(
+            err.to_string().contains("saved for totals on regtest"),
+            "{err}"
+        );
+    }
+    assert_eq!(
+        load_checkpoint(&path, &saved, &mut Totals::default()).unwrap(),
+        9
+    );
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use clap::Parser;
+use kernel::ChainType;
+use swiftsync_research::{Config, Error, HeightRange, REFERENCE_HEIGHT, Result, ScanArgs};
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    scan: ScanArgs,
+}
+
+fn try_parse(args: &[&str], env: &[(&str, &str)]) -> Result<Config> {
+    let env: HashMap<String, String> = env
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let cli = Cli::try_parse_from(std::iter::once("swiftsync").chain(args.iter().copied()))
+        .expect("valid flags");
+    cli.scan.config_from(|key| env.get(key).cloned())
+}
+
+fn parse(args: &[&str], env: &[(&str, &str)]) -> Config {
+    try_parse(args, env).unwrap()
+}
+
+#[test]
+fn range_predicate() {
+    let range = HeightRange::new(700_000, Some(930_000));
+    assert!(!range.contains(699_999));
+    assert!(range.contains(700_000));
+    assert!(range.contains(930_000));
+    assert!(!range.contains(930_001));
+    assert!(range.is_past(930_001));
+    assert!(!HeightRange::new(0, None).is_past(u32::MAX));
+}
+
+#[test]
+fn split_covers_range_in_order() {
+    let ranges = HeightRange::new(700_000, Some(930_000)).split(u32::MAX, 4);
+    assert_eq!(
+        ranges,
+        vec![
+            HeightRange::new(700_000, Some(757_500)),
+            HeightRange::new(757_501, Some(815_001)),
+            HeightRange::new(815_002, Some(872_502)),
+            HeightRange::new(872_503, Some(930_000)),
+        ]
+    );
+    let ranges = HeightRange::new(0, None).split(2, 8);
+    assert_eq!(
+        ranges,
+        vec![
+            HeightRange::new(0, Some(0)),
+            HeightRange::new(1, Some(1)),
+            HeightRange::new(2, Some(2)),
+        ]
+    );
+    assert!(HeightRange::new(10, None).split(9, 4).is_empty());
+}
+
+#[test]
+fn mainnet_defaults_to_reference_height() {
+    let config = parse(&[], &[("BITCOIN_DIR", "/data")]);
+    assert_eq!(config.network, ChainType::Mainnet);
+    assert_eq!(config.range, HeightRange::new(0, Some(REFERENCE_HEIGHT)));
+    assert_eq!(
+        config.network_dir(),
+        Some(std::path::PathBuf::from("/data"))
+    );
+}
+
+#[test]
+fn flags_override_environment() {
+    let config = parse(
+        &[
+            "--network",
+            "signet",
+            "--stop",
+            "200",
+            "--start",
+            "100",
+            "--threads",
+            "8",
+        ],
+        &[
+            ("BITCOIN_DIR", "/data"),
+            ("NETWORK", "regtest"),
+            ("START_HEIGHT", "7"),
+            ("THREADS", "2"),
+        ],
+    );
+    assert_eq!(config.network, ChainType::Signet);
+    assert_eq!(config.range, HeightRange::new(100, Some(200)));
+    assert_eq!(config.threads, 8);
+    assert_eq!(
+        config.network_dir(),
+        Some(std::path::PathBuf::from("/data/signet"))
+    );
+}
+
+#[test]
+fn other_networks_run_to_the_tip() {
+    let config = parse(&["--datadir", "/data", "--network", "testnet4"], &[]);
+    assert_eq!(config.network, ChainType::Testnet4);
+    assert_eq!(config.range, HeightRange::new(0, None));
+}
+
+#[test]
+fn malformed_settings_are_config_errors() {
+    for env in [
+        &[("NETWORK", "bitcoincash")][..],
+        &[("START_HEIGHT", "-1")],
+        &[("THREADS", "many")],
+        &[("RESUME", "1")],
+        &[("CHECKPOINT", "scan.checkpoint"), ("RESUME", "")],
+        &[("CHECKPOINT", "scan.checkpoint"), ("RESUME", "yes please")],
+    ] {
+        let err = try_parse(&[], env).unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "{err}");
+        assert_eq!(err.exit_code(), std::process::ExitCode::from(2));
+    }
+    for args in [
+        &["--network", "bitcoincash"][..],
+        &["--start", "-1"],
+        &["--threads"],
+        &["include"],
+    ] {
+        let args = std::iter::once("swiftsync").chain(args.iter().copied());
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+}
+
+#[test]
+fn resume_is_a_boolean() {
+    let checkpoint = ("CHECKPOINT", "scan.checkpoint");
+    for (value, resume) in [("1", true), ("true", true), ("0", false), ("false", false)] {
+        assert_eq!(parse(&[], &[checkpoint, ("RESUME", value)]).resume, resume);
+    }
+    // The flag wins over the environment.
+    assert!(parse(&["--resume"], &[checkpoint, ("RESUME", "0")]).resume);
+}
+
+#[test]
+fn sequential_commands_reject_threads_and_checkpoints() {
+    parse(&[], &[]).require_sequential("extract-cache").unwrap();
+    let err = parse(
+        &[
+            "--threads",
+            "4",
+            "--checkpoint",
+            "scan.checkpoint",
+            "--resume",
+        ],
+        &[],
+    )
+    .require_sequential("extract-cache")
+    .unwrap_err();
+    assert!(matches!(err, Error::Config(_)), "{err}");
+    assert!(
+        err.to_string()
+            .contains("`extract-cache` scans on a single thread without checkpoints, it does not support threads, checkpoints, resuming"),
+        "{err}"
+    );
+    let err = parse(&[], &[("CHECKPOINT", "scan.checkpoint")])
+        .require_sequential("verify-inputs")
+        .unwrap_err();
+    assert!(
+        err.to_string().ends_with("does not support checkpoints"),
+        "{err}"
+    );
+}
@@ -0,0 +1,239 @@
+use std::io::ErrorKind;
+
+use swiftsync_research::{
+    MAX_SIZE, MAX_VARINT_SIZE, compact_size, compress_amount, decompress_amount, read_amount,
+    read_compact_size, read_varint, size_varint, write_amount, write_compact_size, write_varint,
+};
+
+mod support;
+
+use support::SplitMix64;
+
+const COIN: u64 = 100_000_000;
+const CENT: u64 = 1_000_000;
+const MAX_MONEY: u64 = 21_000_000 * COIN;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn varint(n: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_varint(&mut buf, n).unwrap();
+    buf
+}
+
+fn compact(n: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_compact_size(&mut buf, n).unwrap();
+    buf
+}
+
+// Bitcoin Core's `varints_bitpatterns` vectors.
+#[test]
+fn varint_matches_core_bit_patterns() {
+    for (n, expected) in [
+        (0, "00"),
+        (0x7f, "7f"),
+        (0x80, "8000"),
+        (0x1234, "a334"),
+        (0xffff, "82fe7f"),
+        (0x123456, "c7e756"),
+        (0x80123456, "86ffc7e756"),
+        (0xffffffff, "8efefefe7f"),
+        (0x7fffffffffffffff, "fefefefefefefefe7f"),
+        (0xffffffffffffffff, "80fefefefefefefefe7f"),
+    ] {
+        let bytes = hex(expected);
+        assert_eq!(varint(n), bytes, "{n:#x}");
+        assert_eq!(size_varint(n), bytes.len());
+        assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), n);
+    }
+    assert_eq!(size_varint(u64::MAX), MAX_VARINT_SIZE);
+}
+
+// Bitcoin Core's `varints` round trip, written to and read back from one stream.
+#[test]
+fn varint_round_trips_in_sequence() {
+    let values = (0..100_000u64)
+        .step_by(13)
+        .chain((0..100_000_000_000u64).step_by(999_999_937))
+        .collect::<Vec<_>>();
+    let mut buf = Vec::new();
+    for &n in &values {
+        let before = buf.len();
+        write_varint(&mut buf, n).unwrap();
+        assert_eq!(buf.len() - before, size_varint(n));
+    }
+    let mut reader = buf.as_slice();
+    for &n in &values {
+        assert_eq!(read_varint(&mut reader).unwrap(), n);
+    }
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn varint_writes_into_fixed_buffers() {
+    let mut buf = [0u8; MAX_VARINT_SIZE];
+    let mut slice = &mut buf[..];
+    write_varint(&mut slice, 0x1234).unwrap();
+    assert_eq!(slice.len(), MAX_VARINT_SIZE - 2);
+    assert_eq!(&buf[..2], hex("a334"));
+
+    let mut short = [0u8; 2];
+    let err = write_varint(&mut &mut short[..], 0xffff).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WriteZero);
+}
+
+#[test]
+fn varint_rejects_overflow_and_truncation() {
+    for bytes in [
+        "80fefefefefefefeff00",
+        "fffefefefefefefefe7f",
+        "808080808080808080807f",
+    ] {
+        let err = read_varint(&mut hex(bytes).as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{bytes}");
+    }
+    let err = read_varint(&mut &[0x80u8][..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn compact_size_boundaries() {
+    for (n, expected) in [
+        (0, "00"),
+        (0xfc, "fc"),
+        (0xfd, "fdfd00"),
+        (0xffff, "fdffff"),
+        (0x10000, "fe00000100"),
+        (0xffffffff, "feffffffff"),
+        (0x100000000, "ff0000000001000000"),
+        (u64::MAX, "ffffffffffffffffff"),
+    ] {
+        let bytes = hex(expected);
+        assert_eq!(compact(n), bytes, "{n:#x}");
+        assert_eq!(compact_size(n), bytes.len());
+        assert_eq!(read_compact_size(&mut bytes.as_slice(), false).unwrap(), n);
+    }
+}
+
+// Bitcoin Core's `compactsize` round trip.
+#[test]
+fn compact_size_round_trips_in_sequence() {
+    let values = (1..MAX_SIZE).step_by(2_345).chain([MAX_SIZE]);
+    let mut buf = Vec::new();
+    for n in values.clone() {
+        write_compact_size(&mut buf, n).unwrap();
+    }
+    let mut reader = buf.as_slice();
+    for n in values {
+        assert_eq!(read_compact_size(&mut reader, true).unwrap(), n);
+    }
+    assert!(reader.is_empty());
+}
+
+// Bitcoin Core's `noncanonical` vectors.
+#[test]
+fn compact_size_rejects_non_canonical_encodings() {
+    for bytes in [
+        "fd0000",
+        "fdfc00",
+        "fe00000000",
+        "feffff0000",
+        "ff0000000000000000",
+        "ffffffffff00000000",
+    ] {
+        let err = read_compact_size(&mut hex(bytes).as_slice(), false).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{bytes}");
+    }
+    assert_eq!(
+        read_compact_size(&mut hex("fdfd00").as_slice(), true).unwrap(),
+        0xfd
+    );
+}
+
+#[test]
+fn compact_size_range_check() {
+    let bytes = compact(MAX_SIZE + 1);
+    let err = read_compact_size(&mut bytes.as_slice(), true).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        read_compact_size(&mut bytes.as_slice(), false).unwrap(),
+        MAX_SIZE + 1
+    );
+}
+
+// Bitcoin Core's `compress_amounts` pairs.
+#[test]
+fn amount_compression_matches_core() {
+    for (amount, compressed) in [
+        (0, 0x0),
+        (1, 0x1),
+        (CENT, 0x7),
+        (COIN, 0x9),
+        (50 * COIN, 0x32),
+        (MAX_MONEY, 0x1406f40),
+    ] {
+        assert_eq!(compress_amount(amount), compressed, "{amount}");
+        assert_eq!(decompress_amount(compressed), amount, "{compressed:#x}");
+    }
+}
+
+// Bitcoin Core's `compress_amounts` encode and decode loops.
+#[test]
+fn amount_compression_round_trips() {
+    let amounts = (1..=100_000)
+        .chain((1..=10_000).map(|i| i * CENT))
+        .chain((1..=1_000).map(|i| i * COIN))
+        .chain((1..=MAX_MONEY / (50 * COIN)).map(|i| i * 50 * COIN));
+    for amount in amounts {
+        assert_eq!(
+            decompress_amount(compress_amount(amount)),
+            amount,
+            "{amount}"
+        );
+    }
+    for compressed in 0..100_000 {
+        assert_eq!(
+            compress_amount(decompress_amount(compressed)),
+            compressed,
+            "{compressed:#x}"
+        );
+    }
+}
+
+#[test]
+fn amount_compression_round_trips_up_to_max_money() {
+    for r in SplitMix64::new(0x5eed).take(1_000_000) {
+        let amount = (r % (MAX_MONEY + 1)) / 10u64.pow((r >> 60) as u32 % 10);
+        assert_eq!(
+            decompress_amount(compress_amount(amount)),
+            amount,
+            "{amount}"
+        );
+    }
+    for amount in [MAX_MONEY - 1, MAX_MONEY, MAX_MONEY + 1, u32::MAX as u64] {
+        assert_eq!(decompress_amount(compress_amount(amount)), amount);
+    }
+}
+
+#[test]
+fn amount_codec_round_trips_through_bytes() {
+    let amounts = [0, 1, 546, 12_345_678, CENT, COIN, 50 * COIN, MAX_MONEY];
+    let mut buf = Vec::new();
+    for amount in amounts {
+        let before = buf.len();
+        write_amount(&mut buf, amount).unwrap();
+        assert_eq!(buf.len() - before, size_varint(compress_amount(amount)));
+    }
+    assert_eq!(&buf[buf.len() - 4..], hex("8980dd40"));
+    let mut reader = buf.as_slice();
+    for amount in amounts {
+        assert_eq!(read_amount(&mut reader).unwrap(), amount);
+    }
+    assert!(reader.is_empty());
+}
@@ -0,0 +1,94 @@
+use std::io::ErrorKind;
+
+use swiftsync_research::golomb::{self, MAX_PARAMETER};
+
+mod support;
+
+use support::SplitMix64;
+
+fn round_trip(indices: &[u32]) {
+    let bytes = golomb::encode(indices);
+    assert_eq!(bytes.len(), golomb::encoded_len(indices), "{indices:?}");
+    assert_eq!(
+        golomb::decode(&bytes).unwrap(),
+        (indices.to_vec(), bytes.len())
+    );
+}
+
+#[test]
+fn serializes_gaps_with_the_best_parameter() {
+    // Gaps 3, 0 and 4 take 9 bits with k = 1: 10 1, 0 0, 110 0.
+    assert_eq!(golomb::optimal_parameter(&[3, 4, 9]), 1);
+    assert_eq!(golomb::encode(&[3, 4, 9]), vec![0x03, 0x01, 0xa6, 0x00]);
+    assert_eq!(golomb::encode(&[]), vec![0x00]);
+    // Consecutive indices from zero are all zero gaps, a bit each.
+    assert_eq!(
+        golomb::encode(&(0..16).collect::<Vec<_>>()),
+        vec![0x10, 0x00, 0x00, 0x00]
+    );
+}
+
+#[test]
+fn indices_round_trip() {
+    round_trip(&[]);
+    round_trip(&[0]);
+    round_trip(&[u32::MAX]);
+    round_trip(&[0, u32::MAX]);
+    round_trip(&(0..1_000).collect::<Vec<_>>());
+    round_trip(&(0..1_000).map(|i| i * 1_000 + i % 7).collect::<Vec<_>>());
+    // Random gaps of varying magnitude.
+    let mut index = 0u32;
+    let indices: Vec<u32> = SplitMix64::new(0x5eed)
+        .zip(0..5_000)
+        .map(|(r, i)| {
+            index += 1 + (r >> (50 + i % 14)) as u32;
+            index
+        })
+        .collect();
+    round_trip(&indices);
+}
+
+#[test]
+fn the_parameter_is_optimal() {
+    let indices: Vec<u32> = (0..200).map(|i| i * i).collect();
+    let bits = |k: u8| -> u64 {
+        indices
+            .iter()
+            .scan(None, |prev: &mut Option<u32>, &index| {
+                let gap = prev.map_or(index, |prev| index - prev - 1);
+                *prev = Some(index);
+                Some((gap >> k) as u64 + 1 + k as u64)
+            })
+            .sum()
+    };
+    let fewest = (0..=MAX_PARAMETER).map(bits).min().unwrap();
+    assert_eq!(bits(golomb::optimal_parameter(&indices)), fewest);
+    // A one byte length and the parameter byte.
+    assert_eq!(
+        golomb::encoded_len(&indices),
+        2 + fewest.div_ceil(8) as usize
+    );
+}
+
+#[test]
+fn malformed_input_is_rejected() {
+    let bytes = golomb::encode(&[3, 4, 9]);
+    for len in 0..bytes.len() - 1 {
+        let err = golomb::decode(&bytes[..len]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{len}");
+    }
+    let err = golomb::decode(&[0x01, MAX_PARAMETER + 1, 0x00]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    // Two gaps of 2^31 with k = 31 pass the largest index.
+    let err = golomb::decode(&[0x02, 31, 0x80, 0x00, 0x00, 0x00, 0x40, 0, 0, 0, 0]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    // The largest count with a byte of gaps runs out of input rather than allocating for it.
+    let err = golomb::decode(&[0xfe, 0x00, 0x00, 0x00, 0x02, 0, 0x00]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+#[should_panic(expected = "strictly increasing")]
+fn indices_must_increase() {
+    golomb::encode(&[1, 1]);
+}
@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    process::{Command, ExitCode},
+};
+
+use hintsfile::{EliasFano, Hintsfile, HintsfileBuilder};
+use swiftsync_research::{Error, HintMismatch, HintsBuilder, Report, bitmap, check_round_trip};
+
+mod support;
+
+use support::{FUNDING_HEIGHT, OUTPUTS, TIP};
+
+fn read(hints: &HintsBuilder) -> Hintsfile {
+    let mut buf = Vec::new();
+    hints.write(&mut buf).unwrap();
+    Hintsfile::from_reader(&mut buf.as_slice()).unwrap()
+}
+
+#[test]
+fn only_coinbase_outputs_survive_to_the_tip() {
+    let hints = support::hints(TIP);
+    // The coinbase of height 1 funds the outputs spent afterwards.
+    assert_eq!(hints.unspent_count(), TIP as usize - 1);
+    let file = read(&hints);
+    assert_eq!(file.stop_height(), TIP);
+    assert_eq!(file.indices_at_height(1), Some(Vec::new()));
+    for height in 2..=TIP {
+        assert_eq!(file.indices_at_height(height), Some(vec![0]), "{height}");
+    }
+    assert_eq!(file.indices_at_height(TIP + 1), None);
+}
+
+#[test]
+fn outputs_spent_after_the_stop_height_are_hinted() {
+    let stop = FUNDING_HEIGHT + 3;
+    let file = read(&support::hints(stop));
+    assert_eq!(file.stop_height(), stop);
+    // The funding outputs follow those of the coinbase, outputs 0 to 2 are spent.
+    let coinbase_outputs = support::blocks()[FUNDING_HEIGHT as usize - 1].txdata[0]
+        .output
+        .len() as u32;
+    let mut expected = vec![0];
+    expected.extend(coinbase_outputs + 3..coinbase_outputs + OUTPUTS.len() as u32);
+    assert_eq!(file.indices_at_height(FUNDING_HEIGHT), Some(expected));
+    assert_eq!(file.indices_at_height(1), Some(Vec::new()));
+    // The `OP_RETURN` outputs of the spends are never hinted.
+    assert_eq!(file.indices_at_height(stop), Some(vec![0]));
+}
+
+#[test]
+fn spends_within_a_block_and_duplicate_coinbases() {
+    let p2pkh = [vec![0x76, 0xa9, 20], vec![0; 20], vec![0x88, 0xac]].concat();
+    let op_return = [0x6a, 0x00];
+    let huge = vec![0x51; 10_001];
+    let null = ([0; 32], u32::MAX);
+    let mut hints = HintsBuilder::new();
+    hints.connect_transaction(1, [1; 32], [null], [&p2pkh[..]]);
+    hints.connect_transaction(
+        2,
+        [2; 32],
+        [null],
+        [&p2pkh[..], &op_return[..], &huge[..], &p2pkh[..]],
+    );
+    // Spends the coinbase of height 2 and creates an output spent in the same block.
+    hints.connect_transaction(2, [3; 32], [([2; 32], 0)], [&p2pkh[..]]);
+    hints.connect_transaction(2, [4; 32], [([3; 32], 0)], [&p2pkh[..]]);
+    // Reuses the txid of the coinbase of height 1, whose output is lost.
+    hints.connect_transaction(3, [1; 32], [null], [&p2pkh[..]]);
+    assert_eq!(hints.height(), 3);
+    let file = read(&hints);
+    assert_eq!(file.indices_at_height(1), Some(Vec::new()));
+    assert_eq!(file.indices_at_height(2), Some(vec![3, 5]));
+    assert_eq!(file.indices_at_height(3), Some(vec![0]));
+}
+
+#[test]
+#[should_panic(expected = "height order")]
+fn blocks_must_be_connected_in_order() {
+    let mut hints = HintsBuilder::new();
+    hints.connect_transaction(2, [0; 32], [], [&[0x51][..]]);
+}
+
+#[test]
+fn generated_hints_verify() {
+    for stop in [FUNDING_HEIGHT + 3, TIP] {
+        let hints = support::hints(stop);
+        assert_eq!(hints.verify(&read(&hints)).unwrap(), Vec::new());
+    }
+}
+
+#[test]
+fn verification_reports_every_mismatch() {
+    let hints = support::hints(TIP);
+    let outputs = hints.output_count(7);
+    assert!(outputs > 0);
+    let mut buf = Vec::new();
+    let mut builder = HintsfileBuilder::new(&mut buf).initialize(TIP).unwrap();
+    for height in 1..=TIP {
+        let indices = match height {
+            1 => vec![0],
+            5 => Vec::new(),
+            7 => vec![0, outputs, outputs + 2],
+            _ => vec![0],
+        };
+        builder.append(EliasFano::compress(&indices)).unwrap();
+    }
+    builder.finish().unwrap();
+    let file = Hintsfile::from_reader(&mut buf.as_slice()).unwrap();
+    let mismatches = hints.verify(&file).unwrap();
+    assert_eq!(
+        mismatches,
+        vec![
+            HintMismatch::Extra {
+                height: 1,
+                index: 0
+            },
+            HintMismatch::Missing {
+                height: 5,
+                index: 0
+            },
+            HintMismatch::OutOfRange {
+                height: 7,
+                index: outputs,
+                outputs
+            },
+            HintMismatch::OutOfRange {
+                height: 7,
+                index: outputs + 2,
+                outputs
+            },
+        ]
+    );
+    assert_eq!(
+        mismatches[1].to_string(),
+        "output 0 of block 5 is unspent but not hinted"
+    );
+    let err = Error::WrongHints {
+        path: "bitcoin.hints".into(),
+        stop: TIP,
+        mismatches: mismatches.len(),
+    };
+    assert_eq!(err.exit_code(), ExitCode::from(10));
+}
+
+#[test]
+fn verification_rejects_missing_heights() {
+    let hints = support::hints(TIP);
+    let mut file = read(&hints);
+    file.take_indices(5).unwrap();
+    let err = hints.verify(&file).unwrap_err();
+    assert!(matches!(err, Error::MissingHints { height: 5 }), "{err}");
+    assert_eq!(err.exit_code(), ExitCode::from(5));
+}
+
+#[test]
+fn encodings_must_round_trip() {
+    let bytes = bitmap::encode(&[1, 3]);
+    check_round_trip("bitmap", 7, &[1, 3], &bytes, bitmap::decode).unwrap();
+    let failures = [
+        check_round_trip("bitmap", 7, &[1, 3], &bytes[..1], bitmap::decode),
+        check_round_trip("bitmap", 7, &[1, 4], &bytes, bitmap::decode),
+        check_round_trip(
+            "bitmap",
+            7,
+            &[1, 3],
+            &[&bytes[..], &[0]].concat(),
+            bitmap::decode,
+        ),
+    ];
+    for err in failures.map(Result::unwrap_err) {
+        assert!(
+            matches!(
+                err,
+                Error::RoundTrip {
+                    encoding: "bitmap",
+                    height: 7,
+                    ..
+                }
+            ),
+            "{err}"
+        );
+        assert_eq!(err.exit_code(), ExitCode::from(9));
+    }
+}
+
+#[test]
+fn comparison_writes_a_report() {
+    let dir = std::env::temp_dir().join(format!("hints-compare-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut buf = Vec::new();
+    support::hints(TIP).write(&mut buf).unwrap();
+    fs::write(dir.join("regtest.hints"), buf).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_swiftsync"))
+        .args(["hints", "compare", "--network", "regtest", "--hints"])
+        .arg(dir.join("regtest.hints"))
+        .arg("--json")
+        .arg(dir.join("compare.json"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let report: Report =
+        serde_json::from_slice(&fs::read(dir.join("compare.json")).unwrap()).unwrap();
+    assert_eq!(report.network, "regtest");
+    assert_eq!((report.start_height, report.stop_height), (1, TIP));
+    // Height 1 has no unspent output, every later block only its coinbase.
+    let blocks = TIP as u64 - 1;
+    assert_eq!(report.results["literal_bytes"], 1 + blocks * 3);
+    assert_eq!(report.results["adaptive_bytes"], 1 + blocks * 2);
+    assert_eq!(report.results["adaptive_wins"]["all_spent"], 1);
+    assert_eq!(report.results["adaptive_wins"]["all_unspent"], blocks);
+    assert_eq!(report.results["adaptive_wins"]["bitmap"], 0);
+    fs::remove_dir_all(dir).unwrap();
+}
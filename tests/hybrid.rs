@@ -0,0 +1,124 @@
+use std::io::ErrorKind;
+
+use swiftsync_research::hybrid::{self, Encoding};
+
+fn round_trip(indices: &[u32]) {
+    let (encoding, bytes) = hybrid::encode(indices);
+    assert_eq!(
+        hybrid::decode(&bytes).unwrap(),
+        (indices.to_vec(), bytes.len())
+    );
+    for other in Encoding::ALL {
+        if let Some(other_bytes) = hybrid::encode_with(other, indices) {
+            assert!(
+                bytes.len() <= other_bytes.len(),
+                "{other:?} beats {encoding:?}"
+            );
+            assert_eq!(
+                hybrid::decode(&other_bytes).unwrap(),
+                (indices.to_vec(), other_bytes.len())
+            );
+        }
+    }
+}
+
+#[test]
+fn picks_the_smallest_encoding() {
+    assert_eq!(hybrid::encode(&[]), (Encoding::AllSpent, vec![0x00]));
+    assert_eq!(
+        hybrid::encode(&(0..5).collect::<Vec<_>>()),
+        (Encoding::AllUnspent, vec![0x01, 5])
+    );
+    assert_eq!(hybrid::encode(&[0]), (Encoding::AllUnspent, vec![0x01, 1]));
+    // Ten bits are smaller than gaps or Elias-Fano.
+    assert_eq!(
+        hybrid::encode(&[3, 4, 9]),
+        (Encoding::Bitmap, vec![0x04, 0x0a, 0x18, 0x02])
+    );
+    // Two indices far apart.
+    assert_eq!(
+        hybrid::encode(&[0, 1_000]),
+        (Encoding::RunLengths, vec![0x03, 2, 0, 0x86, 0x68])
+    );
+    let spread: Vec<u32> = (0..1_000).map(|i| i * 10).collect();
+    assert_eq!(hybrid::encode(&spread).0, Encoding::EliasFano);
+}
+
+#[test]
+fn encodings_apply_only_where_they_can() {
+    assert_eq!(hybrid::encode_with(Encoding::AllSpent, &[0]), None);
+    assert_eq!(hybrid::encode_with(Encoding::AllUnspent, &[]), None);
+    assert_eq!(hybrid::encode_with(Encoding::AllUnspent, &[1]), None);
+    assert_eq!(hybrid::encode_with(Encoding::EliasFano, &[u32::MAX]), None);
+    assert_eq!(hybrid::encode_with(Encoding::EliasFano, &[1 << 16]), None);
+    assert_eq!(
+        hybrid::encode_with(Encoding::EliasFano, &[]),
+        Some(vec![0x02, 0x00])
+    );
+}
+
+#[test]
+fn indices_round_trip() {
+    round_trip(&[]);
+    round_trip(&[0]);
+    round_trip(&[7]);
+    round_trip(&[0, 1 << 20]);
+    round_trip(&[3, 4, 9]);
+    round_trip(&(0..1_000).collect::<Vec<_>>());
+    round_trip(&(1..1_000).collect::<Vec<_>>());
+    round_trip(&(0..1_000).map(|i| i * 300 + i % 7).collect::<Vec<_>>());
+    round_trip(&(0..20_000).filter(|i| i % 3 != 1).collect::<Vec<_>>());
+}
+
+#[test]
+fn malformed_input_is_rejected() {
+    for encoding in Encoding::ALL {
+        let indices: &[u32] = match encoding {
+            Encoding::AllSpent => &[],
+            Encoding::AllUnspent => &[0, 1, 2],
+            _ => &[3, 4, 9, 300],
+        };
+        let bytes = hybrid::encode_with(encoding, indices).unwrap();
+        for len in 0..bytes.len() {
+            let err = hybrid::decode(&bytes[..len]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{encoding:?} {len}");
+        }
+    }
+    for bytes in [
+        // An unknown tag.
+        &[0x05][..],
+        // Every output of a block without outputs.
+        &[0x01, 0],
+        // A repeated index.
+        &[0x03, 2, 1, 0],
+        // More Elias-Fano indices than fit below the largest.
+        &[0x02, 5, 2, 0, 0],
+        // Too many low bits for each index.
+        &[0x02, 1, 0xfe, 0, 0, 1, 0],
+        // Upper bits without a set bit to end the only index.
+        &[0x02, 1, 0, 0x00],
+        // A repeated index.
+        &[0x02, 2, 1, 0xc0],
+        // An index below the largest of the header.
+        &[0x02, 1, 1, 0x00, 0x80],
+    ] {
+        let err = hybrid::decode(bytes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{bytes:?}");
+    }
+}
+
+#[test]
+fn elias_fano_payloads_are_bounded_by_the_input() {
+    // A header for 2^31 indices up to 2^32 - 2 without their gigabyte of bits.
+    let bytes = [
+        0x02, 0xfe, 0, 0, 0, 0x80, 0xfe, 0xfe, 0xff, 0xff, 0xff, 0xff,
+    ];
+    let err = hybrid::decode(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+#[should_panic(expected = "strictly increasing")]
+fn indices_must_increase() {
+    hybrid::encode(&[2, 2]);
+}
@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    process::{Command, ExitCode},
+};
+
+use kernel::ChainType;
+use swiftsync_research::{
+    BlockInputs, CacheWriter, Error, Report, SpentBlock, SpentCoin, script::ReconstructableScript,
+    verify_block_inputs,
+};
+
+mod support;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn coin(confirmation_height: u32, is_coinbase: bool, value: i64, script: &str) -> SpentCoin {
+    SpentCoin {
+        confirmation_height,
+        is_coinbase,
+        value,
+        script_pubkey: hex(script),
+    }
+}
+
+fn block() -> SpentBlock {
+    SpentBlock {
+        height: 200,
+        coins: vec![
+            coin(
+                100,
+                true,
+                5_000_000_000,
+                &format!("76a914{}88ac", "11".repeat(20)),
+            ),
+            coin(198, false, 100_000_000, "51"),
+        ],
+    }
+}
+
+#[test]
+fn serializes_age_flag_amount_and_script() {
+    let block = block();
+    let inputs = BlockInputs::from_block(&block).unwrap();
+    assert_eq!(inputs.coins[0].age, 100);
+    assert!(matches!(
+        inputs.coins[0].script,
+        ReconstructableScript::P2pkh(_)
+    ));
+    let mut buf = Vec::new();
+    inputs.encode(&mut buf).unwrap();
+    let expected = format!("8048 02 8049 32 00{} 04 09 0901 51", "11".repeat(20));
+    assert_eq!(buf, hex(&expected.replace(' ', "")));
+    assert_eq!(buf.len(), inputs.encoded_len());
+    assert_eq!(BlockInputs::decode(&mut buf.as_slice()).unwrap(), inputs);
+    assert_eq!(inputs.to_block().unwrap(), block);
+    assert_eq!(verify_block_inputs(&block).unwrap(), buf.len());
+}
+
+#[test]
+fn every_fixture_block_round_trips() {
+    let mut bytes = 0;
+    for block in support::expected_spent_blocks() {
+        bytes += verify_block_inputs(&block).unwrap();
+    }
+    // Every block carries its height and coin count.
+    assert!(bytes > 2 * (support::TIP as usize + 1));
+    let empty = SpentBlock {
+        height: 0,
+        coins: Vec::new(),
+    };
+    assert_eq!(verify_block_inputs(&empty).unwrap(), 2);
+}
+
+#[test]
+fn verification_writes_a_report() {
+    let dir = std::env::temp_dir().join(format!("verify-inputs-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let blocks = support::expected_spent_blocks();
+    let mut writer = CacheWriter::create(&dir.join("regtest.cache"), ChainType::Regtest).unwrap();
+    for block in &blocks {
+        writer.write_block(block).unwrap();
+    }
+    writer.finish().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_swiftsync"))
+        .args(["verify-inputs", "--network", "regtest", "--cache"])
+        .arg(dir.join("regtest.cache"))
+        .arg("--output")
+        .arg(dir.join("block_inputs.csv"))
+        .arg("--json")
+        .arg(dir.join("block_inputs.json"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let report: Report =
+        serde_json::from_slice(&fs::read(dir.join("block_inputs.json")).unwrap()).unwrap();
+    assert_eq!((report.start_height, report.stop_height), (0, support::TIP));
+    let bytes: usize = blocks
+        .iter()
+        .map(|block| verify_block_inputs(block).unwrap())
+        .sum();
+    let coins: usize = blocks.iter().map(|block| block.coins.len()).sum();
+    assert_eq!(report.results["blocks"], blocks.len());
+    assert_eq!(report.results["coins"], coins);
+    assert_eq!(report.results["bytes"], bytes);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn coins_that_cannot_be_serialized_are_mismatches() {
+    for coin in [coin(201, false, 1, "51"), coin(100, false, -1, "51")] {
+        let block = SpentBlock {
+            height: 200,
+            coins: vec![coin],
+        };
+        let err = verify_block_inputs(&block).unwrap_err();
+        assert!(matches!(err, Error::Mismatch { height: 200, .. }), "{err}");
+        assert_eq!(err.exit_code(), ExitCode::from(9));
+    }
+}
+
+#[test]
+fn coins_older_than_their_block_are_rejected() {
+    let mut inputs = BlockInputs::from_block(&block()).unwrap();
+    inputs.coins[1].age = 201;
+    let err = inputs.to_block().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    inputs.coins[1].age = 2;
+    inputs.coins[1].amount = u64::MAX;
+    let err = inputs.to_block().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn malformed_inputs_are_rejected() {
+    // An age of 201 in a block at height 200.
+    let too_old = hex("804801821209");
+    let err = BlockInputs::decode(&mut too_old.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut buf = Vec::new();
+    BlockInputs::from_block(&block())
+        .unwrap()
+        .encode(&mut buf)
+        .unwrap();
+    for len in 0..buf.len() {
+        let err = BlockInputs::decode(&mut &buf[..len]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{len}");
+    }
+}
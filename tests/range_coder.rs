@@ -0,0 +1,96 @@
+use std::io::ErrorKind;
+
+use swiftsync_research::range_coder::{
+    BitModel, BitTree, IntegerModel, RangeDecoder, RangeEncoder,
+};
+
+mod support;
+
+use support::SplitMix64;
+
+fn sample() -> Vec<u64> {
+    // Shifted by varying amounts so every bit length appears.
+    SplitMix64::new(0x5eed)
+        .zip(0..2_000)
+        .map(|(r, i)| r >> (i % 65).min(63))
+        .chain([0, 1, 2, u64::MAX])
+        .collect()
+}
+
+#[test]
+fn integers_round_trip() {
+    let values = sample();
+    let mut encoder = RangeEncoder::new();
+    let mut model = IntegerModel::new();
+    for &value in &values {
+        model.encode(&mut encoder, value);
+    }
+    let bytes = encoder.finish();
+    let mut decoder = RangeDecoder::new(&bytes).unwrap();
+    let mut model = IntegerModel::new();
+    for &value in &values {
+        assert_eq!(model.decode(&mut decoder).unwrap(), value);
+    }
+}
+
+#[test]
+fn symbols_and_direct_bits_round_trip() {
+    let mut encoder = RangeEncoder::new();
+    let mut tree = BitTree::new(4);
+    for symbol in (0..1_000).map(|i| (i * 7 % 11) as u32) {
+        tree.encode(&mut encoder, symbol);
+        encoder.encode_direct(symbol as u64 * 0x0123_4567, 40);
+    }
+    let bytes = encoder.finish();
+    let mut decoder = RangeDecoder::new(&bytes).unwrap();
+    let mut tree = BitTree::new(4);
+    for symbol in (0..1_000).map(|i| (i * 7 % 11) as u32) {
+        assert_eq!(tree.decode(&mut decoder).unwrap(), symbol);
+        assert_eq!(
+            decoder.decode_direct(40).unwrap(),
+            symbol as u64 * 0x0123_4567
+        );
+    }
+}
+
+#[test]
+fn skewed_bits_take_less_than_a_bit_each() {
+    let bits = (0..80_000).map(|i| i % 100 == 0);
+    let mut encoder = RangeEncoder::new();
+    let mut model = BitModel::default();
+    for bit in bits.clone() {
+        encoder.encode_bit(&mut model, bit);
+    }
+    let bytes = encoder.finish();
+    // 80,000 bits with a 1% chance of a one hold about 6,500 bits of information.
+    assert!(bytes.len() < 1_500, "{}", bytes.len());
+    let mut decoder = RangeDecoder::new(&bytes).unwrap();
+    let mut model = BitModel::default();
+    for bit in bits {
+        assert_eq!(decoder.decode_bit(&mut model).unwrap(), bit);
+    }
+}
+
+#[test]
+fn an_empty_stream_is_four_bytes() {
+    assert_eq!(RangeEncoder::new().finish(), vec![0; 4]);
+}
+
+#[test]
+fn truncated_input_is_rejected() {
+    let mut encoder = RangeEncoder::new();
+    let mut model = IntegerModel::new();
+    for value in sample() {
+        model.encode(&mut encoder, value);
+    }
+    let bytes = encoder.finish();
+    let err = RangeDecoder::new(&bytes[..3]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let mut decoder = RangeDecoder::new(&bytes[..bytes.len() / 2]).unwrap();
+    let mut model = IntegerModel::new();
+    let err = (0..)
+        .map(|_| model.decode(&mut decoder))
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
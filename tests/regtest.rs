@@ -0,0 +1,209 @@
+use kernel::ChainType;
+use swiftsync_research::{
+    BlockInputs, BlockSource, CacheReader, CacheWriter, ChainWalker, Chainstate, Config, Error,
+    Result,
+    analysis::{
+        AmountSavings, AmountSummary, CoinAges, CoinAgesSummary, Entropy, EntropyCoding,
+        EntropyCodingSummary, HeightEncodings, HeightEncodingsSummary, ScriptSavings,
+        ScriptSummary, ScriptTypeCount, ScriptTypes, UncompressedP2pk, UncompressedP2pkSummary,
+    },
+    script::ScriptType,
+    verify_block_inputs,
+};
+
+mod support;
+
+use support::{Fixture, TIP};
+
+fn walker(fixture: &Fixture, threads: usize) -> ChainWalker {
+    let mut config = Config::new(ChainType::Regtest);
+    config.data_dir = Some(fixture.data_dir.clone());
+    config.threads = threads;
+    ChainWalker::new(&config).unwrap()
+}
+
+#[test]
+fn regtest_chainstate_yields_fixture_spends() {
+    let fixture = Fixture::build("chainstate");
+    let chainstate = Chainstate::open(fixture.path(), ChainType::Regtest).unwrap();
+    assert_eq!(chainstate.tip(), TIP);
+    let blocks = chainstate
+        .blocks_from(0)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(blocks, fixture.spent_blocks);
+}
+
+#[test]
+fn regtest_analyses_match_expected_numbers() {
+    let fixture = Fixture::build("analyses");
+    for threads in [1, 3] {
+        let walker = walker(&fixture, threads);
+        assert_eq!(
+            walker.scan(AmountSavings::default).unwrap().summary(),
+            AmountSummary {
+                coins: 9,
+                varint_savings_bytes: 57,
+                compact_size_savings_bytes: 55,
+            }
+        );
+        assert_eq!(
+            walker.scan(ScriptSavings::default).unwrap().summary(),
+            ScriptSummary {
+                coins: 9,
+                undo_bytes: 9 * 12 + 25 + 35 + 67 + 25 + 23 + 22 + 34 + 34 + 1,
+                savings_bytes: 5 + 3 + 35 + 5 + 3 + 2 + 2 + 2,
+                unknown_scripts: 1,
+                p2tr_savings_bytes: 2,
+                p2wpkh_savings_bytes: 2,
+                p2wsh_savings_bytes: 2,
+                p2pk_savings_bytes: 3,
+                p2pkh_savings_bytes: 10,
+                p2sh_savings_bytes: 3,
+                p2pk_uncompressed_savings_bytes: 35,
+                multisig_savings_bytes: 0,
+            }
+        );
+        assert_eq!(
+            walker.scan(UncompressedP2pk::default).unwrap().summary(),
+            UncompressedP2pkSummary {
+                coins: 9,
+                uncompressed_p2pk: 1,
+                compressible: 1,
+                hybrid: 0,
+                invalid: 0,
+                savings_bytes: 32,
+            }
+        );
+        let types = walker.scan(ScriptTypes::default).unwrap().summary();
+        let count = |coins, script_bytes| ScriptTypeCount {
+            coins,
+            script_bytes,
+        };
+        assert_eq!(types.coins, 9);
+        assert_eq!(
+            types.types.into_iter().collect::<Vec<_>>(),
+            vec![
+                (ScriptType::P2pk, count(1, 35)),
+                (ScriptType::P2pkUncompressed, count(1, 67)),
+                (ScriptType::P2pkh, count(2, 50)),
+                (ScriptType::P2sh, count(1, 23)),
+                (ScriptType::P2wpkh, count(1, 22)),
+                (ScriptType::P2wsh, count(1, 34)),
+                (ScriptType::P2tr, count(1, 34)),
+                (ScriptType::NonStandard, count(1, 1)),
+            ]
+        );
+        // Coins confirmed at height 101 are spent within 8 blocks, the coinbase coin from
+        // height 1 is spent at 101.
+        assert_eq!(
+            walker.scan(HeightEncodings::default).unwrap().summary(),
+            HeightEncodingsSummary {
+                coins: 9,
+                u32_bytes: 36,
+                varint_bytes: 9,
+                compact_size_bytes: 9,
+                height_coinbase_varint_bytes: 1 + 8 * 2,
+                age_varint_bytes: 9,
+                age_coinbase_varint_bytes: 2 + 8,
+            }
+        );
+        // Every block spends a single coin, so the range coder's flush dominates.
+        assert_eq!(
+            walker.scan(EntropyCoding::default).unwrap().summary(),
+            EntropyCodingSummary {
+                coins: 9,
+                amount_bytes: 15,
+                amount_coded_bytes: 50,
+                age_bytes: 10,
+                age_coded_bytes: 44,
+                tag_bytes: 9,
+                tag_coded_bytes: 36,
+            }
+        );
+        let entropy = walker.scan(Entropy::default).unwrap().summary();
+        assert_eq!(entropy.coins, 9);
+        assert_eq!(
+            [
+                entropy.amounts.symbols,
+                entropy.ages.symbols,
+                entropy.script_types.symbols,
+                entropy.script_lengths.symbols,
+            ],
+            [9, 9, 8, 7]
+        );
+        assert_eq!(entropy.amounts.given_script_type_bits, 2. / 9.);
+        assert_eq!(
+            walker.scan(|| CoinAges::new(false)).unwrap().summary(),
+            CoinAgesSummary {
+                include_coinbase: false,
+                coins: 8,
+                blocks: TIP as u64 + 1,
+                median_age: Some(5),
+                max_age: Some(8),
+            }
+        );
+        assert_eq!(
+            walker.scan(|| CoinAges::new(true)).unwrap().summary(),
+            CoinAgesSummary {
+                include_coinbase: true,
+                coins: 9,
+                blocks: TIP as u64 + 1,
+                median_age: Some(5),
+                max_age: Some(100),
+            }
+        );
+    }
+}
+
+#[test]
+fn regtest_cache_matches_chainstate() {
+    let fixture = Fixture::build("cache");
+    let path = fixture.data_dir.join("spent_coins.cache");
+    let walker = walker(&fixture, 1);
+    let mut writer = CacheWriter::create(&path, walker.network()).unwrap();
+    walker.walk(&mut writer).unwrap();
+    writer.finish().unwrap();
+    let reader = CacheReader::open(&path).unwrap();
+    assert_eq!(reader.heights(), Some((0, TIP)));
+    let blocks = reader.blocks_from(0).collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(blocks, fixture.spent_blocks);
+}
+
+#[test]
+fn regtest_inputs_round_trip_from_chainstate() {
+    let fixture = Fixture::build("inputs");
+    let walker = walker(&fixture, 1);
+    let mut bytes = 0;
+    for block in walker.blocks() {
+        let block = block.unwrap();
+        bytes += verify_block_inputs(&block).unwrap();
+        assert_eq!(
+            BlockInputs::from_block(&block).unwrap().to_block().unwrap(),
+            block
+        );
+    }
+    assert!(bytes > 0);
+}
+
+#[test]
+fn regtest_hints_from_chainstate_match_fixture() {
+    let fixture = Fixture::build("hints");
+    let chainstate = Chainstate::open(fixture.path(), ChainType::Regtest).unwrap();
+    for stop in [support::FUNDING_HEIGHT + 3, TIP] {
+        let (mut generated, mut expected) = (Vec::new(), Vec::new());
+        chainstate
+            .hints(stop)
+            .unwrap()
+            .write(&mut generated)
+            .unwrap();
+        support::hints(stop).write(&mut expected).unwrap();
+        assert_eq!(generated, expected, "{stop}");
+        let file = hintsfile::Hintsfile::from_reader(&mut generated.as_slice()).unwrap();
+        assert_eq!(
+            chainstate.hints(stop).unwrap().verify(&file).unwrap(),
+            Vec::new()
+        );
+    }
+    assert!(matches!(chainstate.hints(TIP + 1), Err(Error::Config(_))));
+}
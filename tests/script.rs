@@ -0,0 +1,453 @@
+use std::io::ErrorKind;
+
+use swiftsync_research::{
+    compact_size,
+    script::{self, ReconstructableScript, ScriptType, classify},
+};
+
+mod support;
+
+use support::{FUNDING_HEIGHT, OUTPUTS, Template};
+
+/// The coordinates of the generator point, and the y-coordinate of its negation.
+const G_X: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const G_Y: &str = "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+const NEG_G_Y: &str = "b7c52588d95c3b9aa25b0403f1eef75702e84bb7597aabe663b82f6f04ef2777";
+
+fn round_trip(script: &[u8]) -> ReconstructableScript {
+    let reconstructable = ReconstructableScript::from_script(script);
+    assert_eq!(reconstructable.to_script().unwrap(), script);
+    let mut buf = Vec::new();
+    reconstructable.encode(&mut buf).unwrap();
+    assert_eq!(buf.len(), reconstructable.encoded_len());
+    assert_eq!(buf[0], reconstructable.tag());
+    let mut reader = buf.as_slice();
+    assert_eq!(
+        ReconstructableScript::decode(&mut reader).unwrap(),
+        reconstructable
+    );
+    assert!(reader.is_empty());
+    reconstructable
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn script_type(template: Template) -> ScriptType {
+    match template {
+        Template::P2pk => ScriptType::P2pk,
+        Template::P2pkUncompressed => ScriptType::P2pkUncompressed,
+        Template::P2pkh => ScriptType::P2pkh,
+        Template::P2sh => ScriptType::P2sh,
+        Template::P2wpkh => ScriptType::P2wpkh,
+        Template::P2wsh => ScriptType::P2wsh,
+        Template::P2tr => ScriptType::P2tr,
+        Template::NonStandard => ScriptType::NonStandard,
+    }
+}
+
+fn tag(template: Template, script: &[u8]) -> u8 {
+    match template {
+        Template::P2pk => script[1],
+        Template::P2pkUncompressed => script::P2PK_UNCOMPRESSED_EVEN + (script[65] & 1),
+        Template::P2pkh => script::P2PKH,
+        Template::P2sh => script::P2SH,
+        Template::P2wpkh => script::P2WPKH,
+        Template::P2wsh => script::P2WSH,
+        Template::P2tr => script::P2TR,
+        Template::NonStandard => script::OTHER,
+    }
+}
+
+#[test]
+fn every_fixture_script_round_trips() {
+    let blocks = support::blocks();
+    let mut scripts = 0;
+    for block in &blocks {
+        for output in block.txdata.iter().flat_map(|tx| &tx.output) {
+            round_trip(output.script_pubkey.as_bytes());
+            scripts += 1;
+        }
+    }
+    assert!(scripts > blocks.len());
+
+    let funding = &blocks[FUNDING_HEIGHT as usize - 1].txdata[1];
+    for (output, &(template, _)) in funding.output.iter().zip(&OUTPUTS) {
+        let script = output.script_pubkey.as_bytes();
+        let reconstructable = round_trip(script);
+        assert_eq!(classify(script), script_type(template));
+        assert_eq!(reconstructable.tag(), tag(template, script), "{template:?}");
+        let raw_len = compact_size(script.len() as u64) + script.len();
+        let expected_savings = match template {
+            Template::P2pkh => 5,
+            Template::P2pkUncompressed => 35,
+            Template::P2sh | Template::P2pk => 3,
+            Template::P2wpkh | Template::P2wsh | Template::P2tr => 2,
+            Template::NonStandard => -1,
+        };
+        assert_eq!(
+            raw_len as i64 - reconstructable.encoded_len() as i64,
+            expected_savings,
+            "{template:?}"
+        );
+    }
+}
+
+#[test]
+fn near_misses_are_stored_whole() {
+    let mut p2pk = vec![33, 0x05];
+    p2pk.extend([0x11; 32]);
+    p2pk.push(0xAC);
+    let mut uncompressed = vec![65, 0x06];
+    uncompressed.extend([0x22; 64]);
+    uncompressed.push(0xAC);
+    let mut p2wpkh = vec![0x00, 20];
+    p2wpkh.extend([0x33; 21]);
+    let mut p2sh = vec![0xA9, 20];
+    p2sh.extend([0x44; 20]);
+    p2sh.push(0x88);
+    let mut witness_v1 = vec![0x51, 20];
+    witness_v1.extend([0x55; 20]);
+    for script in [
+        vec![],
+        vec![0x6A],
+        p2pk,
+        uncompressed,
+        p2wpkh,
+        p2sh,
+        witness_v1,
+    ] {
+        assert!(matches!(
+            round_trip(&script),
+            ReconstructableScript::Other(_)
+        ));
+    }
+    let large = vec![0x6A; 10_000];
+    assert_eq!(round_trip(&large).encoded_len(), 1 + 3 + 10_000);
+}
+
+#[test]
+fn odd_keys_use_their_own_tag() {
+    let mut p2pk = vec![33, 0x03];
+    p2pk.extend([0x77; 32]);
+    p2pk.push(0xAC);
+    let reconstructable = round_trip(&p2pk);
+    assert_eq!(reconstructable.tag(), script::P2PK_ODD);
+    assert_eq!(reconstructable.encoded_len(), 33);
+}
+
+#[test]
+fn malformed_encodings_are_rejected() {
+    let err = ReconstructableScript::decode(&mut &[0xFF][..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    for truncated in [
+        &[script::P2PKH, 0x00][..],
+        &[script::P2TR],
+        &[script::OTHER, 3, 0x6A, 0x6A],
+        &[],
+    ] {
+        let err = ReconstructableScript::decode(&mut &truncated[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{truncated:?}");
+    }
+}
+
+#[test]
+fn classifies_every_template() {
+    let key = "021111111111111111111111111111111111111111111111111111111111111111";
+    let uncompressed = "0422222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222";
+    let hash20 = "33".repeat(20);
+    let hash32 = "44".repeat(32);
+    for (script, expected) in [
+        (format!("21{key}ac"), ScriptType::P2pk),
+        (format!("41{uncompressed}ac"), ScriptType::P2pkUncompressed),
+        (
+            format!("4106{}ac", &uncompressed[2..]),
+            ScriptType::P2pkHybrid,
+        ),
+        (
+            format!("4107{}ac", &uncompressed[2..]),
+            ScriptType::P2pkHybrid,
+        ),
+        (format!("76a914{hash20}88ac"), ScriptType::P2pkh),
+        (format!("a914{hash20}87"), ScriptType::P2sh),
+        (format!("0014{hash20}"), ScriptType::P2wpkh),
+        (format!("0020{hash32}"), ScriptType::P2wsh),
+        (format!("5120{hash32}"), ScriptType::P2tr),
+        ("51024e73".to_string(), ScriptType::P2a),
+        (
+            "51024e74".to_string(),
+            ScriptType::WitnessUnknown { version: 1 },
+        ),
+        (
+            format!("5114{hash20}"),
+            ScriptType::WitnessUnknown { version: 1 },
+        ),
+        (
+            format!("5220{hash32}"),
+            ScriptType::WitnessUnknown { version: 2 },
+        ),
+        (
+            "6002aaaa".to_string(),
+            ScriptType::WitnessUnknown { version: 16 },
+        ),
+        (format!("0015{hash20}33"), ScriptType::NonStandard),
+        (format!("5129{}", "55".repeat(41)), ScriptType::NonStandard),
+        (
+            format!("5121{key}51ae"),
+            ScriptType::Multisig {
+                required: 1,
+                keys: 1,
+            },
+        ),
+        (
+            format!("5221{key}41{uncompressed}21{key}53ae"),
+            ScriptType::Multisig {
+                required: 2,
+                keys: 3,
+            },
+        ),
+        (
+            format!("514c21{key}4d4100{uncompressed}52ae"),
+            ScriptType::Multisig {
+                required: 1,
+                keys: 2,
+            },
+        ),
+        (
+            format!("0111{}0111ae", format!("21{key}").repeat(17)),
+            ScriptType::Multisig {
+                required: 17,
+                keys: 17,
+            },
+        ),
+        (
+            format!("51{}0114ae", format!("21{key}").repeat(20)),
+            ScriptType::Multisig {
+                required: 1,
+                keys: 20,
+            },
+        ),
+        (
+            format!("51{}0115ae", format!("21{key}").repeat(21)),
+            ScriptType::NonStandard,
+        ),
+        (format!("010121{key}51ae"), ScriptType::NonStandard),
+        (format!("514c21{key}51"), ScriptType::NonStandard),
+        (format!("514c22{key}51ae"), ScriptType::NonStandard),
+        (format!("5221{key}51ae"), ScriptType::NonStandard),
+        (format!("5121{key}52ae"), ScriptType::NonStandard),
+        (
+            format!("5121{}51ae", &uncompressed[..66]),
+            ScriptType::NonStandard,
+        ),
+        (
+            format!("5121{key}"),
+            ScriptType::WitnessUnknown { version: 1 },
+        ),
+        (format!("5121{key}51"), ScriptType::NonStandard),
+        ("6a".to_string(), ScriptType::NullData),
+        ("6a04deadbeef".to_string(), ScriptType::NullData),
+        ("6a4c02aabb4d0100ff".to_string(), ScriptType::NullData),
+        ("6a0051".to_string(), ScriptType::NullData),
+        ("6aac".to_string(), ScriptType::NonStandard),
+        ("6a4c".to_string(), ScriptType::NonStandard),
+        ("6a05aa".to_string(), ScriptType::NonStandard),
+        (String::new(), ScriptType::NonStandard),
+        ("51".to_string(), ScriptType::NonStandard),
+    ] {
+        assert_eq!(classify(&hex(&script)), expected, "{script}");
+    }
+}
+
+#[test]
+fn script_type_names_round_trip() {
+    for script_type in [
+        ScriptType::P2pk,
+        ScriptType::P2pkUncompressed,
+        ScriptType::P2pkHybrid,
+        ScriptType::P2pkh,
+        ScriptType::P2sh,
+        ScriptType::P2wpkh,
+        ScriptType::P2wsh,
+        ScriptType::P2tr,
+        ScriptType::P2a,
+        ScriptType::Multisig {
+            required: 2,
+            keys: 3,
+        },
+        ScriptType::NullData,
+        ScriptType::WitnessUnknown { version: 16 },
+        ScriptType::NonStandard,
+    ] {
+        let name = script_type.to_string();
+        assert_eq!(name.parse::<ScriptType>().unwrap(), script_type);
+        let json = serde_json::to_string(&script_type).unwrap();
+        assert_eq!(json, format!("\"{name}\""));
+        assert_eq!(
+            serde_json::from_str::<ScriptType>(&json).unwrap(),
+            script_type
+        );
+    }
+    assert_eq!(
+        ScriptType::Multisig {
+            required: 1,
+            keys: 2
+        }
+        .to_string(),
+        "multisig_1_of_2"
+    );
+    for name in ["p2pk_compressed", "multisig_1_of", "witness_vx_unknown"] {
+        assert!(name.parse::<ScriptType>().is_err(), "{name}");
+    }
+}
+
+#[test]
+fn uncompressed_keys_compress_to_their_x_coordinate() {
+    for (y, tag) in [
+        (G_Y, script::P2PK_UNCOMPRESSED_EVEN),
+        (NEG_G_Y, script::P2PK_UNCOMPRESSED_ODD),
+    ] {
+        let script = hex(&format!("4104{G_X}{y}ac"));
+        let reconstructable = round_trip(&script);
+        assert!(matches!(
+            reconstructable,
+            ReconstructableScript::P2pkUncompressed(_)
+        ));
+        let mut buf = Vec::new();
+        reconstructable.encode(&mut buf).unwrap();
+        assert_eq!(buf, hex(&format!("{tag:02x}{G_X}")));
+    }
+}
+
+#[test]
+fn keys_off_the_curve_are_stored_whole() {
+    // No point has an x-coordinate of 5, and the generator's y does not belong to x = 1.
+    let no_point = format!("{:0>64}", "05");
+    let wrong_y = format!("{:0>64}{G_Y}", "01");
+    for point in [format!("{no_point}{G_Y}"), wrong_y.clone()] {
+        let script = hex(&format!("4104{point}ac"));
+        assert_eq!(classify(&script), ScriptType::P2pkUncompressed);
+        assert!(matches!(
+            round_trip(&script),
+            ReconstructableScript::Other(_)
+        ));
+    }
+    let hybrid = hex(&format!("4106{G_X}{G_Y}ac"));
+    assert!(matches!(
+        round_trip(&hybrid),
+        ReconstructableScript::Other(_)
+    ));
+
+    let point: [u8; 64] = hex(&wrong_y).try_into().unwrap();
+    let err = ReconstructableScript::P2pkUncompressed(point)
+        .encode(&mut Vec::new())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let encoded = hex(&format!("04{no_point}"));
+    let err = ReconstructableScript::decode(&mut encoded.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn bare_multisig_compresses_every_key() {
+    let key = "02".to_string() + &"11".repeat(32);
+    let odd_key = "03".to_string() + &"11".repeat(32);
+    let script = hex(&format!("5221{key}4104{G_X}{G_Y}21{odd_key}53ae"));
+    let reconstructable = round_trip(&script);
+    assert!(matches!(
+        reconstructable,
+        ReconstructableScript::Multisig { required: 2, ref keys } if keys.len() == 3
+    ));
+    let mut buf = Vec::new();
+    reconstructable.encode(&mut buf).unwrap();
+    assert_eq!(buf, hex(&format!("0a12{key}04{G_X}{odd_key}")));
+
+    let sixteen = hex(&format!("60{}60ae", format!("21{key}").repeat(16)));
+    assert_eq!(
+        classify(&sixteen),
+        ScriptType::Multisig {
+            required: 16,
+            keys: 16
+        }
+    );
+    assert_eq!(round_trip(&sixteen).encoded_len(), 2 + 16 * 33);
+
+    for script in [
+        format!("514106{G_X}{G_Y}51ae"),
+        format!("514104{:0>64}{G_Y}51ae", "05"),
+        format!("514c21{key}51ae"),
+        format!("0111{}0111ae", format!("21{key}").repeat(17)),
+    ] {
+        let script = hex(&script);
+        assert!(matches!(classify(&script), ScriptType::Multisig { .. }));
+        assert!(matches!(
+            round_trip(&script),
+            ReconstructableScript::Other(_)
+        ));
+    }
+}
+
+#[test]
+fn uncompressed_p2pk_prefixes_are_rejected() {
+    for prefix in [0x00, 0x04, 0x0a] {
+        let mut key = [0x11; 33];
+        key[0] = prefix;
+        let p2pk = ReconstructableScript::P2pk(key);
+        let err = p2pk.encode(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{prefix}");
+        let err = p2pk.to_script().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{prefix}");
+    }
+    let mut key = [0x11; 33];
+    key[0] = 0x03;
+    let p2pk = ReconstructableScript::P2pk(key);
+    let mut buf = Vec::new();
+    p2pk.encode(&mut buf).unwrap();
+    assert_eq!(
+        ReconstructableScript::decode(&mut buf.as_slice()).unwrap(),
+        p2pk
+    );
+}
+
+#[test]
+fn malformed_multisig_is_rejected() {
+    let key = [0x02; 33].to_vec();
+    for (required, keys) in [(0, 1), (3, 2), (1, 0), (1, 17), (255, 255)] {
+        let multisig = ReconstructableScript::Multisig {
+            required,
+            keys: vec![key.clone(); keys],
+        };
+        let err = multisig.encode(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{required}-of-{keys}");
+        let err = multisig.to_script().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{required}-of-{keys}");
+    }
+    let too_long = ReconstructableScript::Multisig {
+        required: 1,
+        keys: vec![vec![0x02; 300]],
+    };
+    assert_eq!(
+        too_long.to_script().unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    let err = ReconstructableScript::Multisig {
+        required: 1,
+        keys: vec![[0x06; 65].to_vec()],
+    }
+    .encode(&mut Vec::new())
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let mut three_of_two = vec![script::MULTISIG, 0x21];
+    three_of_two.extend([0x02; 66]);
+    let mut hybrid = vec![script::MULTISIG, 0x00, 0x06];
+    hybrid.extend([0x11; 32]);
+    for encoded in [three_of_two, hybrid] {
+        let err = ReconstructableScript::decode(&mut encoded.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{encoded:?}");
+    }
+}
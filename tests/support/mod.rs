@@ -0,0 +1,444 @@
+//! A small deterministic regtest chain, processed through the kernel, that creates and spends one
+//! output of every script template with known amounts and ages.
+//!
+//! Heights 1 through 100 only hold coinbase transactions. The block at [`FUNDING_HEIGHT`] spends
+//! the coinbase of height 1 into one output per [`OUTPUTS`] entry, and output `i` is spent on its
+//! own at height `FUNDING_HEIGHT + 1 + i`, giving it an age of `i + 1`. Spends pay everything to
+//! fees, so the remaining outputs are coinbase outputs and empty `OP_RETURN`s.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use bitcoin::{
+    Amount, Block, BlockHash, CompactTarget, CompressedPublicKey, Network, OutPoint, PublicKey,
+    ScriptBuf, Sequence, Target, Transaction, TxIn, TxMerkleNode, TxOut, Witness,
+    absolute::LockTime,
+    block::{Header, Version},
+    consensus,
+    constants::genesis_block,
+    ecdsa,
+    hashes::Hash,
+    key::{Keypair, TapTweak},
+    opcodes::{OP_0, OP_TRUE},
+    script::Builder,
+    secp256k1::{All, Message, Secp256k1, SecretKey},
+    sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType},
+    taproot, transaction,
+};
+use kernel::{ChainType, ChainstateManager, ContextBuilder};
+use swiftsync_research::{HintsBuilder, SpentBlock, SpentCoin};
+
+pub const SUBSIDY: u64 = 50 * 100_000_000;
+pub const FUNDING_HEIGHT: u32 = 101;
+pub const TIP: u32 = FUNDING_HEIGHT + OUTPUTS.len() as u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    P2pk,
+    P2pkUncompressed,
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    NonStandard,
+}
+
+/// The outputs created at [`FUNDING_HEIGHT`] and their values in satoshis.
+pub const OUTPUTS: [(Template, u64); 8] = [
+    (Template::P2pk, 100_000_000),
+    (Template::P2pkUncompressed, 50_000_000),
+    (Template::P2pkh, 12_345_678),
+    (Template::P2sh, 1_000_000),
+    (Template::P2wpkh, 546),
+    (Template::P2wsh, 2_100_000_000),
+    (Template::P2tr, 10_000),
+    (Template::NonStandard, 777),
+];
+
+/// A regtest data directory holding the fixture chain, removed on drop.
+pub struct Fixture {
+    pub data_dir: PathBuf,
+    /// The coins spent by every block from genesis to [`TIP`].
+    pub spent_blocks: Vec<SpentBlock>,
+}
+
+impl Fixture {
+    /// Build the chain in a fresh data directory named after the test.
+    pub fn build(name: &str) -> Self {
+        let data_dir =
+            std::env::temp_dir().join(format!("swiftsync-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_dir);
+        let blocks_dir = data_dir.join("blocks");
+        std::fs::create_dir_all(&blocks_dir).unwrap();
+        let keys = Keys::new();
+        {
+            let context = ContextBuilder::new()
+                .chain_type(ChainType::Regtest)
+                .build()
+                .unwrap();
+            let chainman = ChainstateManager::new(
+                &context,
+                data_dir.to_str().unwrap(),
+                blocks_dir.to_str().unwrap(),
+            )
+            .unwrap();
+            for block in build_blocks(&keys) {
+                let block = kernel::Block::new(&consensus::serialize(&block)).unwrap();
+                assert!(chainman.process_block(&block).is_new_block());
+            }
+            assert_eq!(chainman.active_chain().height(), TIP as i32);
+        }
+        Self {
+            data_dir,
+            spent_blocks: spent_blocks(&keys),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.data_dir
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
+
+struct Keys {
+    secp: Secp256k1<All>,
+    secret: SecretKey,
+    keypair: Keypair,
+}
+
+impl Keys {
+    fn new() -> Self {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let keypair = Keypair::from_secret_key(&secp, &secret);
+        Self {
+            secp,
+            secret,
+            keypair,
+        }
+    }
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey::new(self.secret.public_key(&self.secp))
+    }
+
+    fn redeem_script() -> ScriptBuf {
+        Builder::new().push_opcode(OP_TRUE).into_script()
+    }
+
+    fn script_pubkey(&self, template: Template) -> ScriptBuf {
+        let public_key = self.public_key();
+        match template {
+            Template::P2pk => ScriptBuf::new_p2pk(&public_key),
+            Template::P2pkUncompressed => {
+                ScriptBuf::new_p2pk(&PublicKey::new_uncompressed(public_key.inner))
+            }
+            Template::P2pkh => ScriptBuf::new_p2pkh(&public_key.pubkey_hash()),
+            Template::P2sh => ScriptBuf::new_p2sh(&Self::redeem_script().script_hash()),
+            Template::P2wpkh => {
+                ScriptBuf::new_p2wpkh(&CompressedPublicKey(public_key.inner).wpubkey_hash())
+            }
+            Template::P2wsh => ScriptBuf::new_p2wsh(&Self::redeem_script().wscript_hash()),
+            Template::P2tr => {
+                let (internal_key, _) = self.keypair.x_only_public_key();
+                ScriptBuf::new_p2tr(&self.secp, internal_key, None)
+            }
+            Template::NonStandard => Self::redeem_script(),
+        }
+    }
+
+    fn ecdsa(&self, digest: [u8; 32]) -> ecdsa::Signature {
+        let message = Message::from_digest(digest);
+        ecdsa::Signature::sighash_all(self.secp.sign_ecdsa(&message, &self.secret))
+    }
+
+    /// Satisfy the single input of `tx`, which spends `prevout` locked by `template`.
+    fn sign(&self, tx: &mut Transaction, template: Template, prevout: &TxOut) {
+        let spk = &prevout.script_pubkey;
+        let legacy = |tx: &Transaction| {
+            let sighash = SighashCache::new(tx)
+                .legacy_signature_hash(0, spk, EcdsaSighashType::All.to_u32())
+                .unwrap();
+            self.ecdsa(sighash.to_byte_array())
+        };
+        match template {
+            Template::P2pk | Template::P2pkUncompressed => {
+                let signature = legacy(tx);
+                tx.input[0].script_sig = Builder::new()
+                    .push_slice(signature.serialize())
+                    .into_script();
+            }
+            Template::P2pkh => {
+                let signature = legacy(tx);
+                tx.input[0].script_sig = Builder::new()
+                    .push_slice(signature.serialize())
+                    .push_key(&self.public_key())
+                    .into_script();
+            }
+            Template::P2sh => {
+                let redeem_script = Self::redeem_script();
+                tx.input[0].script_sig = Builder::new()
+                    .push_slice(
+                        <&bitcoin::script::PushBytes>::try_from(redeem_script.as_bytes()).unwrap(),
+                    )
+                    .into_script();
+            }
+            Template::P2wpkh => {
+                let sighash = SighashCache::new(&*tx)
+                    .p2wpkh_signature_hash(0, spk, prevout.value, EcdsaSighashType::All)
+                    .unwrap();
+                let signature = self.ecdsa(sighash.to_byte_array());
+                tx.input[0].witness = Witness::p2wpkh(&signature, &self.public_key().inner);
+            }
+            Template::P2wsh => {
+                tx.input[0].witness = Witness::from_slice(&[Self::redeem_script().as_bytes()]);
+            }
+            Template::P2tr => {
+                let sighash = SighashCache::new(&*tx)
+                    .taproot_key_spend_signature_hash(
+                        0,
+                        &Prevouts::All(&[prevout]),
+                        TapSighashType::Default,
+                    )
+                    .unwrap();
+                let tweaked = self.keypair.tap_tweak(&self.secp, None).to_keypair();
+                let message = Message::from_digest(sighash.to_byte_array());
+                let signature = taproot::Signature {
+                    signature: self.secp.sign_schnorr_no_aux_rand(&message, &tweaked),
+                    sighash_type: TapSighashType::Default,
+                };
+                tx.input[0].witness = Witness::p2tr_key_spend(&signature);
+            }
+            Template::NonStandard => {}
+        }
+    }
+}
+
+fn coinbase_script_pubkey(keys: &Keys) -> ScriptBuf {
+    keys.script_pubkey(Template::P2pkh)
+}
+
+fn coinbase(keys: &Keys, height: u32, fees: u64) -> Transaction {
+    Transaction {
+        version: transaction::Version::ONE,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            // BIP34 requires the coinbase to start with the height.
+            script_sig: Builder::new()
+                .push_int(height as i64)
+                .push_opcode(OP_0)
+                .into_script(),
+            sequence: Sequence::MAX,
+            witness: Witness::from_slice(&[[0u8; 32]]),
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(SUBSIDY + fees),
+            script_pubkey: coinbase_script_pubkey(keys),
+        }],
+    }
+}
+
+fn spend(
+    keys: &Keys,
+    outpoint: OutPoint,
+    template: Template,
+    prevout: &TxOut,
+    outputs: Vec<TxOut>,
+) -> Transaction {
+    let mut tx = Transaction {
+        version: transaction::Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: outpoint,
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::new(),
+        }],
+        output: outputs,
+    };
+    keys.sign(&mut tx, template, prevout);
+    tx
+}
+
+/// Assemble and mine a block on `prev` with the witness commitment in its coinbase.
+fn mine(prev: BlockHash, height: u32, mut coinbase: Transaction, txs: Vec<Transaction>) -> Block {
+    let bits = CompactTarget::from_consensus(0x207f_ffff);
+    let mut block = Block {
+        header: Header {
+            version: Version::from_consensus(0x2000_0000),
+            prev_blockhash: prev,
+            merkle_root: TxMerkleNode::all_zeros(),
+            time: genesis_block(Network::Regtest).header.time + height * 600,
+            bits,
+            nonce: 0,
+        },
+        txdata: Vec::new(),
+    };
+    block.txdata.push(coinbase.clone());
+    block.txdata.extend(txs.iter().cloned());
+    let witness_root = block.witness_root().unwrap();
+    let commitment = Block::compute_witness_commitment(&witness_root, &[0u8; 32]);
+    let mut script = vec![0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+    script.extend_from_slice(commitment.as_byte_array());
+    coinbase.output.push(TxOut {
+        value: Amount::ZERO,
+        script_pubkey: ScriptBuf::from_bytes(script),
+    });
+    block.txdata[0] = coinbase;
+    block.header.merkle_root = block.compute_merkle_root().unwrap();
+    let target = Target::from_compact(bits);
+    while !target.is_met_by(block.block_hash()) {
+        block.header.nonce += 1;
+    }
+    block
+}
+
+fn funding(keys: &Keys, coinbase: &Transaction) -> Transaction {
+    let outputs = OUTPUTS
+        .iter()
+        .map(|&(template, value)| TxOut {
+            value: Amount::from_sat(value),
+            script_pubkey: keys.script_pubkey(template),
+        })
+        .collect();
+    spend(
+        keys,
+        OutPoint::new(coinbase.compute_txid(), 0),
+        Template::P2pkh,
+        &coinbase.output[0],
+        outputs,
+    )
+}
+
+fn fee(tx: &Transaction, input_value: u64) -> u64 {
+    input_value - tx.output.iter().map(|out| out.value.to_sat()).sum::<u64>()
+}
+
+/// The blocks from height 1 to [`TIP`].
+pub fn blocks() -> Vec<Block> {
+    build_blocks(&Keys::new())
+}
+
+fn build_blocks(keys: &Keys) -> Vec<Block> {
+    let mut prev = genesis_block(Network::Regtest).block_hash();
+    let mut blocks = Vec::new();
+    let mut first_coinbase = None;
+    let mut funding_tx = None;
+    for height in 1..=TIP {
+        let txs = if height < FUNDING_HEIGHT {
+            Vec::new()
+        } else if height == FUNDING_HEIGHT {
+            let coinbase: &Transaction = first_coinbase.as_ref().unwrap();
+            let tx = funding(keys, coinbase);
+            funding_tx = Some(tx.clone());
+            vec![tx]
+        } else {
+            let funding_tx: &Transaction = funding_tx.as_ref().unwrap();
+            let index = (height - FUNDING_HEIGHT - 1) as usize;
+            let (template, _) = OUTPUTS[index];
+            let burn = TxOut {
+                value: Amount::ZERO,
+                script_pubkey: ScriptBuf::new_op_return([]),
+            };
+            vec![spend(
+                keys,
+                OutPoint::new(funding_tx.compute_txid(), index as u32),
+                template,
+                &funding_tx.output[index],
+                vec![burn],
+            )]
+        };
+        let fees = match height.cmp(&FUNDING_HEIGHT) {
+            std::cmp::Ordering::Less => 0,
+            std::cmp::Ordering::Equal => fee(&txs[0], SUBSIDY),
+            std::cmp::Ordering::Greater => OUTPUTS[(height - FUNDING_HEIGHT - 1) as usize].1,
+        };
+        let block = mine(prev, height, coinbase(keys, height, fees), txs);
+        if height == 1 {
+            first_coinbase = Some(block.txdata[0].clone());
+        }
+        prev = block.block_hash();
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// The coins spent by every block from genesis to [`TIP`], as the kernel reports them.
+pub fn expected_spent_blocks() -> Vec<SpentBlock> {
+    spent_blocks(&Keys::new())
+}
+
+fn spent_blocks(keys: &Keys) -> Vec<SpentBlock> {
+    (0..=TIP)
+        .map(|height| {
+            let coins = if height == FUNDING_HEIGHT {
+                vec![SpentCoin {
+                    confirmation_height: 1,
+                    is_coinbase: true,
+                    value: SUBSIDY as i64,
+                    script_pubkey: coinbase_script_pubkey(keys).into_bytes(),
+                }]
+            } else if height > FUNDING_HEIGHT {
+                let (template, value) = OUTPUTS[(height - FUNDING_HEIGHT - 1) as usize];
+                vec![SpentCoin {
+                    confirmation_height: FUNDING_HEIGHT,
+                    is_coinbase: false,
+                    value: value as i64,
+                    script_pubkey: keys.script_pubkey(template).into_bytes(),
+                }]
+            } else {
+                Vec::new()
+            };
+            SpentBlock { height, coins }
+        })
+        .collect()
+}
+
+/// The hints at `stop` of the fixture blocks, connected without the kernel.
+pub fn hints(stop: u32) -> HintsBuilder {
+    let mut hints = HintsBuilder::new();
+    for (height, block) in (1..=stop).zip(blocks()) {
+        for tx in &block.txdata {
+            hints.connect_transaction(
+                height,
+                tx.compute_txid().to_byte_array(),
+                tx.input.iter().map(|input| {
+                    let outpoint = input.previous_output;
+                    (outpoint.txid.to_byte_array(), outpoint.vout)
+                }),
+                tx.output
+                    .iter()
+                    .map(|output| output.script_pubkey.as_bytes()),
+            );
+        }
+    }
+    hints
+}
+
+/// SplitMix64, so tests can sample values of every magnitude without a dependency.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+}
+
+impl Iterator for SplitMix64 {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Some(z ^ (z >> 31))
+    }
+}
@@ -0,0 +1,128 @@
+use swiftsync_research::{
+    HeightRange, Merge, SpentBlock, SpentCoin, SpentCoinVisitor, walk, walk_parallel,
+};
+
+#[derive(Debug, Default, PartialEq)]
+struct Recorder {
+    blocks: Vec<u32>,
+    coins: Vec<(u32, u32)>,
+}
+
+impl SpentCoinVisitor for Recorder {
+    fn visit_block(&mut self, height: u32) {
+        self.blocks.push(height);
+    }
+
+    fn visit_coin(&mut self, height: u32, coin: &SpentCoin) {
+        self.coins.push((height, coin.confirmation_height));
+    }
+}
+
+impl Merge for Recorder {
+    fn merge(&mut self, other: Self) {
+        self.blocks.extend(other.blocks);
+        self.coins.extend(other.coins);
+    }
+}
+
+fn coin(confirmation_height: u32) -> SpentCoin {
+    SpentCoin {
+        confirmation_height,
+        is_coinbase: false,
+        value: 50_000,
+        script_pubkey: vec![0x51],
+    }
+}
+
+#[test]
+fn visits_blocks_and_coins_in_order() {
+    let blocks = vec![
+        SpentBlock {
+            height: 1,
+            coins: Vec::new(),
+        },
+        SpentBlock {
+            height: 2,
+            coins: vec![coin(1), coin(0)],
+        },
+        SpentBlock {
+            height: 3,
+            coins: vec![coin(2)],
+        },
+    ];
+    let mut recorder = Recorder::default();
+    walk(
+        blocks.into_iter().map(Ok),
+        HeightRange::default(),
+        &mut recorder,
+    )
+    .unwrap();
+    assert_eq!(recorder.blocks, vec![1, 2, 3]);
+    assert_eq!(recorder.coins, vec![(2, 1), (2, 0), (3, 2)]);
+}
+
+#[test]
+fn stops_after_reference_height() {
+    let blocks = (929_998..=930_002).map(|height| SpentBlock {
+        height,
+        coins: vec![coin(height - 1)],
+    });
+    let mut recorder = Recorder::default();
+    walk(
+        blocks.into_iter().map(Ok),
+        HeightRange::default(),
+        &mut recorder,
+    )
+    .unwrap();
+    assert_eq!(recorder.blocks, vec![929_998, 929_999, 930_000]);
+    assert_eq!(recorder.coins.len(), 3);
+}
+
+#[test]
+fn visits_only_the_configured_window() {
+    let blocks = (0..20).map(|height| SpentBlock {
+        height,
+        coins: Vec::new(),
+    });
+    let mut recorder = Recorder::default();
+    walk(
+        blocks.into_iter().map(Ok),
+        HeightRange::new(5, Some(8)),
+        &mut recorder,
+    )
+    .unwrap();
+    assert_eq!(recorder.blocks, vec![5, 6, 7, 8]);
+    let blocks = (0..20).map(|height| SpentBlock {
+        height,
+        coins: Vec::new(),
+    });
+    let mut recorder = Recorder::default();
+    walk(
+        blocks.into_iter().map(Ok),
+        HeightRange::new(15, None),
+        &mut recorder,
+    )
+    .unwrap();
+    assert_eq!(recorder.blocks, (15..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn parallel_walk_matches_sequential() {
+    let chain = (0..=100)
+        .map(|height| SpentBlock {
+            height,
+            coins: (0..height % 7).map(coin).collect(),
+        })
+        .collect::<Vec<_>>();
+    let range = HeightRange::new(3, Some(97));
+    let mut sequential = Recorder::default();
+    walk(chain.iter().cloned().map(Ok), range, &mut sequential).unwrap();
+    for threads in [1, 2, 5, 16, 200] {
+        let ranges = range.split(100, threads);
+        let parallel = walk_parallel(&ranges, Recorder::default, |range| {
+            chain[range.start as usize..].iter().cloned().map(Ok)
+        })
+        .unwrap();
+        assert_eq!(parallel, sequential);
+    }
+}